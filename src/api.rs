@@ -0,0 +1,342 @@
+//! The library API: loading an archive, choosing tweets with a [`Filter`], and deleting them with
+//! a [`Deleter`]. The command line tool is built on the same pieces, with the options it parses
+//! from the command line.
+
+use crate::{
+    keep_tweet, load_account, load_tweets, run, Command, DateRange, Opts, Progress, State, Summary,
+    Target, WrappedTweet,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{FromArgMatches, IntoApp};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Returns the options a `damae tweets` run starts out with, before any are given. The archive
+/// path is only a placeholder, the archive is loaded separately.
+fn default_opts() -> Opts {
+    let matches = crate::Cli::into_app()
+        .try_get_matches_from(["damae", "tweets", "archive"])
+        .expect("the tweets subcommand needs nothing but the archive path");
+    Command::from_arg_matches(&matches)
+        .and_then(Command::into_opts)
+        .expect("the tweets subcommand runs with the default options")
+}
+
+/// The tweets in a Twitter archive, and the account it was exported from.
+pub struct Archive {
+    tweets: Vec<ArchiveTweet>,
+    username: Option<String>,
+    account_id: Option<u64>,
+}
+
+impl Archive {
+    /// Reads the tweets from an archive, either the downloaded `.zip` file or the directory it
+    /// was unzipped into. Tweets with an ID that isn't a number, which twitter's API can't act
+    /// on, are left out.
+    pub fn load(path: impl AsRef<Path>) -> Result<Archive, String> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Err(format!("{} does not exist", path.display()));
+        }
+        let account = load_account(path)?;
+        let tweets = load_tweets(path, |t| {
+            t.tweet.id.parse::<u64>().is_ok()
+                && t.tweet
+                    .retweeted_status_id
+                    .as_ref()
+                    .is_none_or(|id| id.parse::<u64>().is_ok())
+        })?;
+        Ok(Archive {
+            tweets: tweets.into_iter().map(ArchiveTweet).collect(),
+            username: account.as_ref().map(|account| account.username.clone()),
+            account_id: account.and_then(|account| account.account_id.parse().ok()),
+        })
+    }
+
+    /// Returns every tweet in the archive.
+    pub fn tweets(&self) -> &[ArchiveTweet] {
+        &self.tweets
+    }
+
+    /// Returns the tweets in the archive that pass the filter. The date filters are relative to
+    /// when this is called.
+    pub fn filtered<'a>(&'a self, filter: &'a Filter) -> impl Iterator<Item = &'a ArchiveTweet> {
+        let dates = DateRange::from_opts(&filter.opts, Utc::now());
+        let account_id = self.account_id.map(|id| id.to_string());
+        self.tweets.iter().filter(move |tweet| {
+            filter.keeps(
+                tweet,
+                self.username.as_deref(),
+                account_id.as_deref(),
+                dates,
+            )
+        })
+    }
+
+    /// Returns the handle of the account the archive was exported from, if the archive has it.
+    pub fn username(&self) -> Option<&str> {
+        self.username.as_deref()
+    }
+
+    /// Returns the ID of the account the archive was exported from, if the archive has it.
+    pub fn account_id(&self) -> Option<u64> {
+        self.account_id
+    }
+}
+
+/// A tweet from the archive.
+#[derive(Clone)]
+pub struct ArchiveTweet(WrappedTweet);
+
+impl ArchiveTweet {
+    /// Returns the ID of the tweet.
+    pub fn id(&self) -> &str {
+        &self.0.tweet.id
+    }
+
+    /// Returns the text of the tweet, or an empty string if the archive doesn't have it.
+    pub fn text(&self) -> &str {
+        self.0.tweet.full_text.as_deref().unwrap_or("")
+    }
+
+    /// Returns when the tweet was posted, falling back to the time encoded in its ID.
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        self.0.tweet.created_at()
+    }
+
+    /// Returns whether the tweet is a reply to another tweet.
+    pub fn is_reply(&self) -> bool {
+        self.0.tweet.in_reply_to_status_id.is_some()
+    }
+
+    /// Returns whether the tweet is a retweet, which deleting undoes.
+    pub fn is_retweet(&self) -> bool {
+        self.0.tweet.is_retweet()
+    }
+
+    /// Returns how many likes the tweet had when the archive was exported.
+    pub fn favorite_count(&self) -> u64 {
+        self.0.tweet.favorite_count
+    }
+
+    /// Returns how many retweets the tweet had when the archive was exported.
+    pub fn retweet_count(&self) -> u64 {
+        self.0.tweet.retweet_count
+    }
+
+    /// Returns the entry exactly as it appears in the archive, including the fields that have no
+    /// accessor.
+    pub fn raw(&self) -> &Value {
+        &self.0.raw
+    }
+}
+
+/// A filter added with [`Filter::matching`].
+type CustomFilter = Box<dyn Fn(&ArchiveTweet) -> bool + Send + Sync>;
+
+/// Chooses which tweets are deleted, with the same filters as the command line tool. A tweet
+/// has to pass every filter that's set, including the ones added with [`Filter::matching`].
+pub struct Filter {
+    opts: Opts,
+    custom: Vec<CustomFilter>,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter::new()
+    }
+}
+
+impl Filter {
+    /// Returns a filter that every tweet passes.
+    pub fn new() -> Filter {
+        Filter {
+            opts: default_opts(),
+            custom: Vec::new(),
+        }
+    }
+
+    /// Only keeps tweets posted before the start of the given day, like `--before`.
+    pub fn before(mut self, date: NaiveDate) -> Self {
+        self.opts.tweets.older_than = Some(date);
+        self
+    }
+
+    /// Only keeps tweets posted after the start of the given day, like `--after`.
+    pub fn after(mut self, date: NaiveDate) -> Self {
+        self.opts.tweets.newer_than = Some(date);
+        self
+    }
+
+    /// Only keeps tweets that are more than this many days old, like `--older-than-days`.
+    pub fn older_than_days(mut self, days: u32) -> Self {
+        self.opts.tweets.older_than_days = Some(days);
+        self
+    }
+
+    /// Only keeps tweets posted in the given calendar year, like `--year`. Can be called again
+    /// to keep tweets from any of the years.
+    pub fn year(mut self, year: i32) -> Self {
+        self.opts.tweets.year.push(year);
+        self
+    }
+
+    /// Only keeps tweets whose text contains the given string, like `--contains`.
+    pub fn contains(mut self, text: impl Into<String>) -> Self {
+        self.opts.tweets.contains = Some(text.into());
+        self
+    }
+
+    /// Leaves out tweets whose text contains the given string, like `--exclude-contains`. Can be
+    /// called again to leave out tweets containing any of the strings.
+    pub fn exclude_contains(mut self, text: impl Into<String>) -> Self {
+        self.opts.tweets.exclude_contains.push(text.into());
+        self
+    }
+
+    /// Ignores case in [`Filter::contains`] and [`Filter::exclude_contains`], like
+    /// `--ignore-case`.
+    pub fn ignore_case(mut self) -> Self {
+        self.opts.tweets.ignore_case = true;
+        self
+    }
+
+    /// Only keeps replies, like `--replies-only`.
+    pub fn replies_only(mut self) -> Self {
+        self.opts.tweets.replies_only = true;
+        self
+    }
+
+    /// Leaves out replies, like `--top-level-only`.
+    pub fn top_level_only(mut self) -> Self {
+        self.opts.tweets.top_level_only = true;
+        self
+    }
+
+    /// Only keeps retweets, like `--retweets-only`.
+    pub fn retweets_only(mut self) -> Self {
+        self.opts.tweets.retweets_only = true;
+        self
+    }
+
+    /// Leaves out retweets, like `--exclude-retweets`.
+    pub fn exclude_retweets(mut self) -> Self {
+        self.opts.tweets.exclude_retweets = true;
+        self
+    }
+
+    /// Leaves out tweets with at least this many likes, like `--min-favorites`.
+    pub fn min_favorites(mut self, favorites: u64) -> Self {
+        self.opts.tweets.min_favorites = Some(favorites);
+        self
+    }
+
+    /// Leaves out tweets with at least this many retweets, like `--min-retweets`.
+    pub fn min_retweets(mut self, retweets: u64) -> Self {
+        self.opts.tweets.min_retweets = Some(retweets);
+        self
+    }
+
+    /// Only keeps tweets for which the function returns `true`.
+    pub fn matching(mut self, f: impl Fn(&ArchiveTweet) -> bool + Send + Sync + 'static) -> Self {
+        self.custom.push(Box::new(f));
+        self
+    }
+
+    /// Returns whether the tweet, from the given archive, passes the filter. To check every tweet
+    /// in an archive, use [`Archive::filtered`].
+    pub fn matches(&self, tweet: &ArchiveTweet, archive: &Archive) -> bool {
+        let account_id = archive.account_id.map(|id| id.to_string());
+        let dates = DateRange::from_opts(&self.opts, Utc::now());
+        self.keeps(tweet, archive.username(), account_id.as_deref(), dates)
+    }
+
+    fn keeps(
+        &self,
+        tweet: &ArchiveTweet,
+        username: Option<&str>,
+        account_id: Option<&str>,
+        dates: DateRange,
+    ) -> bool {
+        keep_tweet(&tweet.0, &self.opts, dates, None, username, account_id)
+            && self.custom.iter().all(|f| f(tweet))
+    }
+}
+
+/// Deletes tweets from an account, retrying transient failures and waiting out the rate limit
+/// like the command line tool does.
+pub struct Deleter {
+    token: crate::Token,
+    user_id: u64,
+    opts: Opts,
+    stop: Arc<AtomicBool>,
+}
+
+impl Deleter {
+    /// Returns a deleter for the account the token belongs to, whose ID is `user_id`. Progress is
+    /// only reported through the `log` crate.
+    pub fn new(token: crate::Token, user_id: u64) -> Deleter {
+        let mut opts = default_opts();
+        opts.common.progress_style = Some(Progress::None);
+        Deleter {
+            token,
+            user_id,
+            opts,
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Only reports what would be deleted, like `--dry-run`.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.opts.common.dry_run = dry_run;
+        self
+    }
+
+    /// Sets how many deletions run at once, like `--max-tasks`.
+    ///
+    /// # Panics
+    ///
+    /// If `max_tasks` is 0.
+    pub fn max_tasks(mut self, max_tasks: usize) -> Self {
+        assert!(max_tasks > 0, "max_tasks must be at least 1");
+        self.opts.common.max_tasks = max_tasks;
+        self
+    }
+
+    /// Sets how many times a failed deletion is retried, like `--max-retries`.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.opts.common.max_retries = max_retries;
+        self
+    }
+
+    /// Limits the deletions to this many per minute, like `--rate-limit`.
+    pub fn rate_limit(mut self, per_minute: u32) -> Self {
+        self.opts.common.rate_limit = per_minute;
+        self
+    }
+
+    /// Stops a [`Deleter::delete`] in progress once the deletions in flight finish. The tweets
+    /// that were never tried are counted in [`Summary::remaining`].
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+
+    /// Deletes the tweets in the archive that pass the filter, and undoes the retweets.
+    pub async fn delete(&self, archive: &Archive, filter: &Filter) -> Summary {
+        let targets = archive
+            .filtered(filter)
+            .map(|tweet| Target::from(tweet.0.clone()))
+            .collect::<Vec<_>>();
+        run(
+            &targets,
+            &self.token,
+            self.user_id,
+            &self.opts,
+            State::default(),
+            self.stop.clone(),
+        )
+        .await
+    }
+}
@@ -0,0 +1,845 @@
+//! The tweet filters: every option of `damae tweets` that chooses which tweets are deleted, with
+//! its help text, an example, and the check it makes. [`FILTERS`] is the only place they're
+//! described, the command line's help for these options and `damae help-filters` both come from
+//! it, and [`crate::keep_tweet`] runs the check of every filter in it. The options that need the
+//! whole archive at once, like --keep-self-threads, are listed here too but checked once it's
+//! loaded.
+
+use crate::{text_contains, year_in, DateRange, Tweet, TweetOpts};
+use chrono::{Local, Timelike};
+use regex::Regex;
+
+/// The heading a filter is listed under by `damae help-filters`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Category {
+    Replies,
+    Retweets,
+    Quotes,
+    Media,
+    Mentions,
+    Dates,
+    Ids,
+    Text,
+    Length,
+    Popularity,
+    Client,
+    Amount,
+    Protected,
+    Invert,
+}
+
+impl Category {
+    const ALL: [Category; 14] = [
+        Category::Replies,
+        Category::Retweets,
+        Category::Quotes,
+        Category::Media,
+        Category::Mentions,
+        Category::Dates,
+        Category::Ids,
+        Category::Text,
+        Category::Length,
+        Category::Popularity,
+        Category::Client,
+        Category::Amount,
+        Category::Protected,
+        Category::Invert,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            Category::Replies => "Replies",
+            Category::Retweets => "Retweets",
+            Category::Quotes => "Quote tweets",
+            Category::Media => "Media",
+            Category::Mentions => "Mentions and hashtags",
+            Category::Dates => "Dates and IDs",
+            Category::Ids => "Lists of tweet IDs",
+            Category::Text => "Text",
+            Category::Length => "Length",
+            Category::Popularity => "Likes and retweets",
+            Category::Client => "Client",
+            Category::Amount => "How many are deleted",
+            Category::Protected => "Protected tweets",
+            Category::Invert => "Inverting the filters",
+        }
+    }
+}
+
+/// An option that sets a filter.
+pub struct Flag {
+    /// The long name of the option, without the leading dashes.
+    pub name: &'static str,
+    pub help: &'static str,
+    /// The option as it would be passed to `damae tweets`, with a value if it takes one.
+    pub example: &'static str,
+}
+
+/// What a filter can look at besides the tweet itself.
+pub struct Context<'a> {
+    pub opts: &'a TweetOpts,
+    pub dates: DateRange,
+    pub match_regex: Option<&'a Regex>,
+    /// The archive's account, which --self-quotes-only compares quoted tweets against.
+    pub username: Option<&'a str>,
+    /// The ID of the archive's account, which --self-replies-only and --exclude-self-replies
+    /// compare replies against.
+    pub account_id: Option<&'a str>,
+}
+
+/// A filter on the tweets in the archive, set by one or more options that are checked together
+/// (like the media options, where a tweet only has to match one of them).
+pub struct TweetFilter {
+    pub category: Category,
+    pub flags: &'static [Flag],
+    /// Returns whether the tweet passes the filter. A filter whose options weren't given passes
+    /// every tweet.
+    pub keeps: fn(&Tweet, &Context) -> bool,
+}
+
+/// The check of a filter that needs the whole archive, which passes every tweet on its own.
+/// `run_cli` applies those filters once the archive is loaded.
+fn whole_archive(_: &Tweet, _: &Context) -> bool {
+    true
+}
+
+/// Every tweet filter, in the order they're checked and listed.
+pub static FILTERS: &[TweetFilter] = &[
+    TweetFilter {
+        category: Category::Replies,
+        flags: &[
+            Flag {
+                name: "replies-only",
+                help: "If enabled, the tool will only delete reply tweets",
+                example: "--replies-only",
+            },
+            Flag {
+                name: "top-level-only",
+                help: "If enabled, the tool will only delete top-level tweets",
+                example: "--top-level-only",
+            },
+        ],
+        keeps: |t, c| {
+            if t.in_reply_to_status_id.is_some() {
+                !c.opts.top_level_only
+            } else {
+                !c.opts.replies_only
+            }
+        },
+    },
+    TweetFilter {
+        category: Category::Replies,
+        flags: &[Flag {
+            name: "include-replies-to",
+            help: "If enabled, the tool will only delete replies to the given account, with or \
+                    without the leading @ (ignoring case)",
+            example: "--include-replies-to @jack",
+        }],
+        keeps: |t, c| {
+            c.opts.include_replies_to.as_ref().is_none_or(|handle| {
+                let handle = handle.strip_prefix('@').unwrap_or(handle);
+                t.in_reply_to_screen_name
+                    .as_ref()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(handle))
+            })
+        },
+    },
+    TweetFilter {
+        category: Category::Replies,
+        flags: &[
+            Flag {
+                name: "self-replies-only",
+                help: "If enabled, the tool will only delete replies to your own tweets, like the \
+                    rest of your threads",
+                example: "--self-replies-only",
+            },
+            Flag {
+                name: "exclude-self-replies",
+                help: "If enabled, the tool will not delete replies to your own tweets, only \
+                    replies to others and top-level tweets (with --replies-only, only replies to \
+                    others)",
+                example: "--replies-only --exclude-self-replies",
+            },
+        ],
+        // Without data/account.js the account is only known after logging in, so the tweets are
+        // checked then instead.
+        keeps: |t, c| {
+            c.account_id.is_none_or(|account_id| {
+                keeps_self_replies(c.opts, t.in_reply_to_user_id.as_deref(), account_id)
+            })
+        },
+    },
+    TweetFilter {
+        category: Category::Replies,
+        flags: &[
+            Flag {
+                name: "report-orphans",
+                help: "Print how many of the selected tweets are orphans, replies to a tweet that \
+                    isn't in the archive (someone else's, or one of yours that's already gone)",
+                example: "--report-orphans",
+            },
+            Flag {
+                name: "keep-orphans",
+                help:
+                    "If enabled, the tool will not delete orphans, replies to a tweet that isn't \
+                    in the archive",
+                example: "--keep-orphans",
+            },
+            Flag {
+                name: "orphans-only",
+                help: "If enabled, the tool will only delete orphans, replies to a tweet that \
+                    isn't in the archive",
+                example: "--orphans-only",
+            },
+        ],
+        keeps: whole_archive,
+    },
+    TweetFilter {
+        category: Category::Retweets,
+        flags: &[
+            Flag {
+                name: "retweets-only",
+                help: "If enabled, the tool will only delete retweets",
+                example: "--retweets-only",
+            },
+            Flag {
+                name: "exclude-retweets",
+                help: "If enabled, the tool will not delete retweets",
+                example: "--exclude-retweets",
+            },
+        ],
+        keeps: |t, c| {
+            if t.is_retweet() {
+                !c.opts.exclude_retweets
+            } else {
+                !c.opts.retweets_only
+            }
+        },
+    },
+    TweetFilter {
+        category: Category::Retweets,
+        flags: &[Flag {
+            name: "keep-retweets-of",
+            help: "If enabled, the tool will not delete retweets of the given account, with or \
+                    without the leading @ (ignoring case, can be repeated)",
+            example: "--retweets-only --keep-retweets-of @nasa",
+        }],
+        keeps: |t, c| {
+            !(t.is_retweet()
+                && t.retweeted_username().is_some_and(|username| {
+                    c.opts.keep_retweets_of.iter().any(|handle| {
+                        let handle = handle.strip_prefix('@').unwrap_or(handle);
+                        username.eq_ignore_ascii_case(handle)
+                    })
+                }))
+        },
+    },
+    TweetFilter {
+        category: Category::Quotes,
+        flags: &[
+            Flag {
+                name: "quotes-only",
+                help: "If enabled, the tool will only delete quote tweets",
+                example: "--quotes-only",
+            },
+            Flag {
+                name: "exclude-quotes",
+                help: "If enabled, the tool will not delete quote tweets",
+                example: "--exclude-quotes",
+            },
+        ],
+        keeps: |t, c| {
+            if t.is_quote() {
+                !c.opts.exclude_quotes
+            } else {
+                !c.opts.quotes_only
+            }
+        },
+    },
+    TweetFilter {
+        category: Category::Quotes,
+        flags: &[Flag {
+            name: "self-quotes-only",
+            help: "If enabled, the tool will only delete tweets that quote one of your own \
+                    tweets",
+            example: "--self-quotes-only",
+        }],
+        // Without data/account.js the account is only known after logging in, so the tweets are
+        // checked then instead.
+        keeps: |t, c| {
+            !c.opts.self_quotes_only
+                || c.username.is_none_or(|username| {
+                    t.quoted_username()
+                        .is_some_and(|quoted| quoted.eq_ignore_ascii_case(username))
+                })
+        },
+    },
+    TweetFilter {
+        category: Category::Media,
+        flags: &[
+            Flag {
+                name: "has-photo",
+                help: "If enabled, the tool will only delete tweets with a photo attached \
+                    (combined with the other media filters, tweets matching any of them are \
+                    deleted)",
+                example: "--has-photo --has-gif",
+            },
+            Flag {
+                name: "has-video",
+                help: "If enabled, the tool will only delete tweets with a video attached \
+                    (combined with the other media filters, tweets matching any of them are \
+                    deleted)",
+                example: "--has-video",
+            },
+            Flag {
+                name: "has-gif",
+                help: "If enabled, the tool will only delete tweets with a GIF attached (combined \
+                    with the other media filters, tweets matching any of them are deleted)",
+                example: "--has-gif",
+            },
+            Flag {
+                name: "no-media",
+                help: "If enabled, the tool will only delete tweets without any media attached \
+                    (combined with the other media filters, tweets matching any of them are \
+                    deleted)",
+                example: "--no-media",
+            },
+        ],
+        keeps: |t, c| {
+            let media_filters = [
+                (c.opts.has_photo, "photo"),
+                (c.opts.has_video, "video"),
+                (c.opts.has_gif, "animated_gif"),
+            ];
+            if !c.opts.no_media && !media_filters.iter().any(|(enabled, _)| *enabled) {
+                return true;
+            }
+            (c.opts.no_media && t.has_no_media())
+                || media_filters
+                    .iter()
+                    .any(|(enabled, kind)| *enabled && t.has_media(kind))
+        },
+    },
+    TweetFilter {
+        category: Category::Mentions,
+        flags: &[
+            Flag {
+                name: "has-mentions",
+                help: "If enabled, the tool will only delete tweets that @mention someone",
+                example: "--has-mentions",
+            },
+            Flag {
+                name: "no-mentions",
+                help: "If enabled, the tool will only delete tweets that don't @mention anyone",
+                example: "--no-mentions",
+            },
+        ],
+        keeps: |t, c| {
+            if t.entities.user_mentions.is_empty() {
+                !c.opts.has_mentions
+            } else {
+                !c.opts.no_mentions
+            }
+        },
+    },
+    TweetFilter {
+        category: Category::Mentions,
+        flags: &[
+            Flag {
+                name: "has-hashtags",
+                help: "If enabled, the tool will only delete tweets with a hashtag",
+                example: "--has-hashtags",
+            },
+            Flag {
+                name: "hashtag",
+                help: "If enabled, the tool will only delete tweets with the given hashtag, \
+                    ignoring case (can be repeated to delete tweets with any of them)",
+                example: "--hashtag tbt --hashtag nowplaying",
+            },
+        ],
+        keeps: |t, c| {
+            let hashtags = &t.entities.hashtags;
+            (!hashtags.is_empty() || !c.opts.has_hashtags)
+                && (c.opts.hashtag.is_empty()
+                    || c.opts.hashtag.iter().any(|wanted| {
+                        let wanted = wanted.strip_prefix('#').unwrap_or(wanted);
+                        hashtags
+                            .iter()
+                            .any(|hashtag| hashtag.text.to_lowercase() == wanted.to_lowercase())
+                    }))
+        },
+    },
+    TweetFilter {
+        category: Category::Dates,
+        flags: &[
+            Flag {
+                name: "before",
+                help: "If enabled, the tool will only delete tweets that are older than the given \
+                    date (in the format YYYY-MM-DD)",
+                example: "--before 2020-01-01",
+            },
+            Flag {
+                name: "after",
+                help: "If enabled, the tool will only delete tweets that are newer than the given \
+                    date (in the format YYYY-MM-DD)",
+                example: "--after 2019-06-01 --before 2019-09-01",
+            },
+            Flag {
+                name: "older-than-days",
+                help: "If enabled, the tool will only delete tweets that are more than this many \
+                    days old",
+                example: "--older-than-days 30",
+            },
+            Flag {
+                name: "newer-than-days",
+                help: "If enabled, the tool will only delete tweets that are less than this many \
+                    days old",
+                example: "--newer-than-days 7",
+            },
+        ],
+        // Without a date there's no telling whether the tweet is in range.
+        keeps: |t, c| match t.created_at() {
+            Some(created_at) => c.dates.contains(created_at),
+            None => c.dates.is_unbounded(),
+        },
+    },
+    TweetFilter {
+        category: Category::Dates,
+        flags: &[
+            Flag {
+                name: "since-id",
+                help: "If enabled, the tool will only delete tweets with an ID greater than the \
+                    given one, which means they were posted after it",
+                example: "--since-id 1234567890123456789",
+            },
+            Flag {
+                name: "max-id",
+                help: "If enabled, the tool will only delete tweets with an ID less than or equal \
+                    to the given one, which means they were posted before it or are that tweet",
+                example: "--max-id 1234567890123456789",
+            },
+        ],
+        keeps: |t, c| {
+            if c.opts.since_id.is_none() && c.opts.max_id.is_none() {
+                return true;
+            }
+            // Without a numeric ID there's no telling whether the tweet is in range.
+            t.id.parse::<u64>().is_ok_and(|id| {
+                c.opts.since_id.is_none_or(|since_id| id > since_id)
+                    && c.opts.max_id.is_none_or(|max_id| id <= max_id)
+            })
+        },
+    },
+    TweetFilter {
+        category: Category::Dates,
+        flags: &[
+            Flag {
+                name: "year",
+                help: "If enabled, the tool will only delete tweets posted in the given calendar \
+                    year, in the --timezone (can be repeated to delete tweets from any of them)",
+                example: "--year 2012 --year 2013",
+            },
+            Flag {
+                name: "between-hours",
+                help:
+                    "If enabled, the tool will only delete tweets posted between the given hours \
+                    of the day, in 24-hour time (e.g. 22-04 for 10pm to 4am; the end hour is \
+                    not included)",
+                example: "--between-hours 22-04",
+            },
+            Flag {
+                name: "timezone",
+                help: "Time zone used for --between-hours, --year, and the breakdown by year, as \
+                    an IANA name like Europe/Berlin [default: the system time zone]",
+                example: "--between-hours 22-04 --timezone Europe/Berlin",
+            },
+        ],
+        keeps: |t, c| {
+            if c.opts.year.is_empty() && c.opts.between_hours.is_none() {
+                return true;
+            }
+            let created_at = match t.created_at() {
+                Some(created_at) => created_at,
+                None => return false,
+            };
+            let in_year = c.opts.year.is_empty()
+                || c.opts.year.contains(&year_in(c.opts.timezone, &created_at));
+            let in_hours = c.opts.between_hours.is_none_or(|between_hours| {
+                let hour = match c.opts.timezone {
+                    Some(tz) => created_at.with_timezone(&tz).hour(),
+                    None => created_at.with_timezone(&Local).hour(),
+                };
+                between_hours.contains(hour)
+            });
+            in_year && in_hours
+        },
+    },
+    TweetFilter {
+        category: Category::Text,
+        flags: &[
+            Flag {
+                name: "contains",
+                help: "If enabled, the tool will only delete tweets whose text contains the given \
+                    string",
+                example: "--contains giveaway",
+            },
+            Flag {
+                name: "exclude-contains",
+                help: "If enabled, the tool will not delete tweets whose text contains the given \
+                    string (can be repeated to keep tweets containing any of them)",
+                example: "--exclude-contains thread --exclude-contains resume",
+            },
+            Flag {
+                name: "match-regex",
+                help: "If enabled, the tool will only delete tweets whose text matches the given \
+                    regular expression (when combined with --contains, tweets must satisfy \
+                    both)",
+                example: "--match-regex '^(RT|MT) '",
+            },
+            Flag {
+                name: "ignore-case",
+                help: "If enabled, text matching with --contains, --exclude-contains, and \
+                    --match-regex will ignore case",
+                example: "--contains giveaway --ignore-case",
+            },
+        ],
+        keeps: |t, c| {
+            let ignore_case = c.opts.ignore_case;
+            let text = t.full_text.as_deref();
+            c.opts.contains.as_ref().is_none_or(|contains| {
+                text.is_some_and(|text| text_contains(text, contains, ignore_case))
+            }) && !text.is_some_and(|text| {
+                c.opts
+                    .exclude_contains
+                    .iter()
+                    .any(|exclude| text_contains(text, exclude, ignore_case))
+            }) && c
+                .match_regex
+                .is_none_or(|regex| text.is_some_and(|text| regex.is_match(text)))
+        },
+    },
+    TweetFilter {
+        category: Category::Length,
+        flags: &[
+            Flag {
+                name: "min-length",
+                help: "If enabled, the tool will only delete tweets whose text is at least this \
+                    many characters long",
+                example: "--min-length 200",
+            },
+            Flag {
+                name: "max-length",
+                help: "If enabled, the tool will only delete tweets whose text is at most this \
+                    many characters long",
+                example: "--max-length 10",
+            },
+            Flag {
+                name: "length-mode",
+                help:
+                    "Whether --min-length and --max-length count all of a tweet's text, or leave \
+                    out the @mentions at the start of a reply and trailing media links",
+                example: "--max-length 10 --length-mode display",
+            },
+        ],
+        keeps: |t, c| {
+            if c.opts.min_length.is_none() && c.opts.max_length.is_none() {
+                return true;
+            }
+            // Without any text there's no telling how long the tweet is.
+            t.length(c.opts.length_mode).is_some_and(|length| {
+                c.opts.min_length.is_none_or(|min| length >= min)
+                    && c.opts.max_length.is_none_or(|max| length <= max)
+            })
+        },
+    },
+    TweetFilter {
+        category: Category::Popularity,
+        flags: &[
+            Flag {
+                name: "min-favorites",
+                help: "If enabled, the tool will keep tweets with at least this many likes, and \
+                    only delete tweets with fewer",
+                example: "--min-favorites 100",
+            },
+            Flag {
+                name: "min-retweets",
+                help:
+                    "If enabled, the tool will keep tweets with at least this many retweets, and \
+                    only delete tweets with fewer",
+                example: "--min-retweets 20",
+            },
+            Flag {
+                name: "delete-popular",
+                help: "If enabled, --min-favorites and --min-retweets are reversed: the tool will \
+                    only delete tweets that reach one of the thresholds, and keep the rest",
+                example: "--min-favorites 1000 --delete-popular",
+            },
+        ],
+        keeps: |t, c| {
+            if c.opts.min_favorites.is_none() && c.opts.min_retweets.is_none() {
+                return true;
+            }
+            // A tweet is popular if it reaches any of the thresholds that were given.
+            let popular = c
+                .opts
+                .min_favorites
+                .is_some_and(|min| t.favorite_count >= min)
+                || c.opts
+                    .min_retweets
+                    .is_some_and(|min| t.retweet_count >= min);
+            popular == c.opts.delete_popular
+        },
+    },
+    TweetFilter {
+        category: Category::Client,
+        flags: &[
+            Flag {
+                name: "from-client",
+                help: "If enabled, the tool will only delete tweets posted from a client whose \
+                    name contains the given string",
+                example: "--from-client 'Twitter for iPhone'",
+            },
+            Flag {
+                name: "exclude-client",
+                help:
+                    "If enabled, the tool will not delete tweets posted from a client whose name \
+                    contains the given string",
+                example: "--exclude-client TweetDeck",
+            },
+        ],
+        keeps: |t, c| {
+            let client = t.client();
+            c.opts
+                .from_client
+                .as_ref()
+                .is_none_or(|from| client.is_some_and(|client| client.contains(from.as_str())))
+                && !c.opts.exclude_client.as_ref().is_some_and(|exclude| {
+                    client.is_some_and(|client| client.contains(exclude.as_str()))
+                })
+        },
+    },
+    TweetFilter {
+        category: Category::Client,
+        flags: &[Flag {
+            name: "list-clients",
+            help:
+                "Print every client tweets in the archive were posted from, with how many tweets \
+                came from each, and exit",
+            example: "--list-clients",
+        }],
+        keeps: whole_archive,
+    },
+    TweetFilter {
+        category: Category::Protected,
+        flags: &[
+            Flag {
+                name: "keep-self-threads",
+                help: "If enabled, the tool will not delete any tweet in a thread of replies to \
+                    your own tweets, including the tweet that started it",
+                example: "--keep-self-threads",
+            },
+            Flag {
+                name: "dedupe-threads",
+                help: "If enabled, the tool will keep the last tweet of each of your self-threads \
+                    (a reply to your own tweet that none of your tweets reply to) and delete the \
+                    rest as usual",
+                example: "--dedupe-threads",
+            },
+        ],
+        keeps: whole_archive,
+    },
+    TweetFilter {
+        category: Category::Protected,
+        flags: &[
+            Flag {
+                name: "delete-pinned",
+                help: "If enabled, the tool will delete the tweet pinned to your profile along \
+                    with the others, instead of keeping it",
+                example: "--delete-pinned",
+            },
+            Flag {
+                name: "keep-first",
+                help: "If enabled, the tool will never delete your N oldest tweets in the \
+                    archive, whatever the other filters say",
+                example: "--keep-first 100",
+            },
+            Flag {
+                name: "preserve-media-tweets",
+                help: "If enabled, the tool will never delete a tweet with a photo, video, or GIF \
+                    attached, whatever the other filters say",
+                example: "--preserve-media-tweets",
+            },
+        ],
+        keeps: whole_archive,
+    },
+    TweetFilter {
+        category: Category::Invert,
+        flags: &[Flag {
+            name: "invert",
+            help: "If enabled, the tool will delete the tweets that the other tweet filters would \
+                keep, and keep the ones they would delete (the ID lists, --keep-self-threads, \
+                --dedupe-threads, --keep-first, --preserve-media-tweets, and the pinned tweet \
+                still protect tweets as usual, and --keep-orphans and --orphans-only are checked \
+                after it)",
+            example: "--invert --replies-only",
+        }],
+        keeps: whole_archive,
+    },
+    TweetFilter {
+        category: Category::Ids,
+        flags: &[
+            Flag {
+                name: "keep-ids",
+                help: "If enabled, the tool will never delete the tweets listed in the given \
+                    file, either one ID per line or a JSON array from a twitter archive (like \
+                    data/like.js)",
+                example: "--keep-ids keep.txt",
+            },
+            Flag {
+                name: "keep-id",
+                help: "If enabled, the tool will never delete the tweet with this ID (can be \
+                    repeated)",
+                example: "--keep-id 1234567890",
+            },
+        ],
+        keeps: whole_archive,
+    },
+    TweetFilter {
+        category: Category::Ids,
+        flags: &[
+            Flag {
+                name: "only-ids",
+                help: "If enabled, the tool will only delete the tweets listed in the given file, \
+                    one ID per line",
+                example: "--only-ids ids.txt",
+            },
+            Flag {
+                name: "id",
+                help: "If enabled, the tool will only delete the tweet with this ID (can be \
+                    repeated, and combined with --only-ids)",
+                example: "--id 1234567890 --id 1234567891",
+            },
+            Flag {
+                name: "ids-from-stdin",
+                help: "If enabled, the tool will only delete the tweets whose IDs are piped in on \
+                    stdin, one per line (combined with --only-ids and --id). Blank lines and \
+                    lines starting with # are skipped",
+                example: "--ids-from-stdin",
+            },
+        ],
+        keeps: whole_archive,
+    },
+    TweetFilter {
+        category: Category::Ids,
+        flags: &[
+            Flag {
+                name: "replay",
+                help: "If enabled, the tool will only delete the tweets recorded in the given log \
+                    file from a previous run (written with --log-file) with one of the statuses \
+                    given with --status",
+                example: "--replay damae.log --status failed",
+            },
+            Flag {
+                name: "status",
+                help: "The statuses of the tweets in the --replay log to act on (can be repeated)",
+                example: "--replay damae.log --status failed --status dry_run",
+            },
+        ],
+        keeps: whole_archive,
+    },
+    TweetFilter {
+        category: Category::Amount,
+        flags: &[Flag {
+            name: "limit",
+            help: "If enabled, the tool will delete at most this many tweets, after all other \
+                filters are applied (tweets are taken in --order, or archive order if it isn't \
+                given)",
+            example: "--limit 100",
+        }],
+        keeps: whole_archive,
+    },
+    TweetFilter {
+        category: Category::Amount,
+        flags: &[
+            Flag {
+                name: "sample-fraction",
+                help: "If enabled, the tool will only delete this fraction of the selected \
+                    tweets, from 0 to 1, picked at random as --sample-strategy says",
+                example: "--sample-fraction 0.2",
+            },
+            Flag {
+                name: "sample-strategy",
+                help: "How --sample-fraction picks the tweets [default: uniform]",
+                example: "--sample-fraction 0.2 --sample-strategy age-weighted",
+            },
+        ],
+        keeps: whole_archive,
+    },
+];
+
+/// Returns whether a tweet passes --self-replies-only and --exclude-self-replies, given the
+/// account it replies to and the ID of your own.
+pub fn keeps_self_replies(
+    opts: &TweetOpts,
+    in_reply_to_user_id: Option<&str>,
+    account_id: &str,
+) -> bool {
+    if in_reply_to_user_id == Some(account_id) {
+        !opts.exclude_self_replies
+    } else {
+        !opts.self_replies_only
+    }
+}
+
+/// Returns the help text of a filter option, for the command line's help.
+///
+/// # Panics
+///
+/// If there's no option with that name in [`FILTERS`], which the help of every filter option
+/// needs.
+pub fn help(name: &str) -> &'static str {
+    FILTERS
+        .iter()
+        .flat_map(|filter| filter.flags)
+        .find(|flag| flag.name == name)
+        .unwrap_or_else(|| panic!("--{} is missing from the filter registry", name))
+        .help
+}
+
+/// Prints every filter option with an example, grouped by what they look at.
+pub fn print_help() {
+    println!(
+        "Filters choose which tweets `damae tweets` deletes. A tweet has to pass every filter"
+    );
+    println!("that's given to be deleted.");
+    for category in Category::ALL {
+        println!();
+        println!("{}:", category.title());
+        for flag in FILTERS
+            .iter()
+            .filter(|filter| filter.category == category)
+            .flat_map(|filter| filter.flags)
+        {
+            println!("    --{}", flag.name);
+            for line in wrap(flag.help, 92) {
+                println!("        {}", line);
+            }
+            println!("        e.g. damae tweets {} <ARCHIVE_PATH>", flag.example);
+        }
+    }
+}
+
+/// Splits text into lines of at most `width` characters, breaking only between words.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::<String>::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines
+}
@@ -0,0 +1,5764 @@
+//! Deletes tweets, likes, direct messages, blocks, and mutes, using the Twitter data archive to
+//! find them. Besides the `damae` command line tool, the filtering and deletion can be used from
+//! other programs:
+//!
+//! ```no_run
+//! use chrono::NaiveDate;
+//! use damae::{Archive, Deleter, Filter, KeyPair, Token};
+//!
+//! # async fn example() -> Result<(), String> {
+//! let archive = Archive::load("twitter-archive.zip")?;
+//! let filter = Filter::new()
+//!     .before(NaiveDate::from_ymd(2020, 1, 1))
+//!     .exclude_retweets()
+//!     .matching(|tweet| !tweet.text().contains("#keep") && tweet.favorite_count() < 100);
+//! let token = Token::Access {
+//!     consumer: KeyPair::new("consumer key", "consumer secret"),
+//!     access: KeyPair::new("access token", "access token secret"),
+//! };
+//! let summary = Deleter::new(token, archive.account_id().ok_or("no account.js")?)
+//!     .dry_run(true)
+//!     .delete(&archive, &filter)
+//!     .await;
+//! println!("{} deleted, {} failed", summary.deleted, summary.failed);
+//! # Ok(())
+//! # }
+//! ```
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono::{Datelike, TimeZone};
+use chrono_tz::Tz;
+use clap::{AppSettings, ArgEnum, Args, FromArgMatches, IntoApp, Parser, Subcommand};
+use colour::*;
+use dialoguer::{Confirm, Input};
+use env_logger::fmt::Color;
+use env_logger::Env;
+use futures::StreamExt;
+use indicatif::{self, HumanDuration, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use log::{debug, error, info, warn, Level};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use regex::{Regex, RegexBuilder};
+use serde::de::{DeserializeOwned, DeserializeSeed, IgnoredAny, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::io::{BufRead, BufReader, IsTerminal, Read, Write};
+use std::marker::PhantomData;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+mod api;
+mod filters;
+mod metrics;
+mod net;
+mod oauth2;
+mod remote;
+mod tui;
+
+pub use api::{Archive, ArchiveTweet, Deleter, Filter};
+pub use egg_mode::{KeyPair, Token};
+
+#[derive(Clone, Debug)]
+struct WrappedTweet {
+    tweet: Tweet,
+    /// The entry exactly as it appears in the archive, including fields the tool doesn't use.
+    raw: Value,
+}
+#[derive(Clone, Deserialize, Debug)]
+struct Tweet {
+    #[serde(deserialize_with = "deserialize_id")]
+    id: String,
+    in_reply_to_status_id: Option<String>,
+    in_reply_to_screen_name: Option<String>,
+    in_reply_to_user_id: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_created_at")]
+    created_at: Option<Result<DateTime<Utc>, InvalidTimestamp>>,
+    full_text: Option<String>,
+    retweeted_status_id: Option<String>,
+    retweeted_status: Option<RetweetedStatus>,
+    extended_entities: Option<ExtendedEntities>,
+    #[serde(default)]
+    entities: Entities,
+    #[serde(default, deserialize_with = "deserialize_count")]
+    favorite_count: u64,
+    #[serde(default, deserialize_with = "deserialize_count")]
+    retweet_count: u64,
+    /// The client the tweet was posted from, as an HTML link.
+    source: Option<String>,
+    quoted_status_id_str: Option<String>,
+    quoted_status_permalink: Option<QuotedStatusPermalink>,
+    /// The part of `full_text` shown as the body of the tweet, leaving out the leading
+    /// @mentions of a reply and trailing media links.
+    #[serde(default, deserialize_with = "deserialize_range")]
+    display_text_range: Option<(usize, usize)>,
+}
+#[derive(Clone, Deserialize, Debug)]
+struct RetweetedStatus {
+    user: RetweetedUser,
+}
+#[derive(Clone, Deserialize, Debug)]
+struct RetweetedUser {
+    screen_name: String,
+}
+#[derive(Clone, Deserialize, Debug)]
+struct QuotedStatusPermalink {
+    /// The full link to the quoted tweet, like `https://twitter.com/<username>/status/<id>`.
+    expanded: String,
+}
+#[derive(Clone, Deserialize, Debug, Default)]
+struct Entities {
+    /// Only whether a tweet mentions anyone matters, not who.
+    #[serde(default)]
+    user_mentions: Vec<IgnoredAny>,
+    #[serde(default)]
+    hashtags: Vec<Hashtag>,
+}
+#[derive(Clone, Deserialize, Debug)]
+struct Hashtag {
+    /// The hashtag without the leading `#`.
+    text: String,
+}
+#[derive(Clone, Deserialize, Debug)]
+struct ExtendedEntities {
+    media: Vec<Media>,
+}
+#[derive(Clone, Deserialize, Debug)]
+struct Media {
+    media_url_https: String,
+    /// `photo`, `video`, or `animated_gif`.
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    video_info: Option<VideoInfo>,
+}
+#[derive(Clone, Deserialize, Debug)]
+struct VideoInfo {
+    variants: Vec<VideoVariant>,
+}
+#[derive(Clone, Deserialize, Debug)]
+struct VideoVariant {
+    bitrate: Option<String>,
+    content_type: String,
+    url: String,
+}
+
+impl Media {
+    /// Returns the URL of the highest resolution version of this media item. Videos and GIFs
+    /// use the mp4 variant with the highest bitrate, photos use the original upload.
+    fn best_url(&self) -> String {
+        let best_variant = self.video_info.as_ref().and_then(|video_info| {
+            video_info
+                .variants
+                .iter()
+                .filter(|v| v.content_type == "video/mp4")
+                .max_by_key(|v| {
+                    v.bitrate
+                        .as_deref()
+                        .and_then(|b| b.parse::<u64>().ok())
+                        .unwrap_or(0)
+                })
+        });
+        match best_variant {
+            Some(variant) => variant.url.clone(),
+            None => format!("{}?name=orig", self.media_url_https),
+        }
+    }
+}
+
+/// The names each field of [`Tweet`] has gone by in archive exports, most preferred first.
+const TWEET_FIELD_NAMES: &[(&str, &[&str])] = &[
+    ("id", &["id_str", "id", "rest_id", "tweet_id"]),
+    ("full_text", &["full_text", "text"]),
+    (
+        "in_reply_to_status_id",
+        &["in_reply_to_status_id_str", "in_reply_to_status_id"],
+    ),
+    (
+        "in_reply_to_user_id",
+        &["in_reply_to_user_id_str", "in_reply_to_user_id"],
+    ),
+    (
+        "retweeted_status_id",
+        &["retweeted_status_id_str", "retweeted_status_id"],
+    ),
+    (
+        "quoted_status_id_str",
+        &["quoted_status_id_str", "quoted_status_id"],
+    ),
+];
+
+impl Tweet {
+    /// Reads a tweet from an entry of the archive's tweet data. Exports have wrapped the tweet
+    /// in a `tweet` object or not, nested its fields in a `legacy` object like twitter's web
+    /// API does, and named the same field differently (`id_str` or `id`, `full_text` or
+    /// `text`), so the entry is normalized to one shape before it's deserialized. IDs stored as
+    /// numbers are read as strings.
+    fn from_value(entry: &Value) -> Result<Tweet, String> {
+        let inner = entry
+            .get("tweet")
+            .filter(|t| t.is_object())
+            .unwrap_or(entry);
+        let mut fields = inner
+            .as_object()
+            .ok_or("expected each tweet to be an object")?
+            .clone();
+        if let Some(Value::Object(legacy)) = fields.remove("legacy") {
+            for (key, value) in legacy {
+                fields.entry(key).or_insert(value);
+            }
+        }
+        // Twitter's API embeds the retweeted tweet rather than giving its ID on its own.
+        if !fields.contains_key("retweeted_status_id") {
+            let id = fields
+                .get("retweeted_status")
+                .and_then(|status| status.get("id_str"))
+                .cloned();
+            if let Some(id) = id {
+                fields.insert("retweeted_status_id".to_string(), id);
+            }
+        }
+        for (field, names) in TWEET_FIELD_NAMES {
+            let value = names
+                .iter()
+                .filter_map(|name| fields.get(*name))
+                .find(|value| !value.is_null())
+                .cloned();
+            match value {
+                Some(Value::Number(n)) => fields.insert(field.to_string(), n.to_string().into()),
+                Some(value) => fields.insert(field.to_string(), value),
+                None => None,
+            };
+        }
+        serde_json::from_value(Value::Object(fields)).map_err(|e| e.to_string())
+    }
+
+    /// Returns whether this tweet is a retweet, falling back to the `RT @` text prefix for
+    /// archives that don't record the retweeted status.
+    fn is_retweet(&self) -> bool {
+        self.retweeted_status_id.is_some()
+            || self
+                .full_text
+                .as_deref()
+                .is_some_and(|text| text.starts_with("RT @"))
+    }
+
+    /// Returns the username of the account whose tweet this tweet retweets, from the retweeted
+    /// status if the archive has it or else from the `RT @<username>:` text prefix.
+    fn retweeted_username(&self) -> Option<&str> {
+        if let Some(status) = &self.retweeted_status {
+            return Some(&status.user.screen_name);
+        }
+        let rest = self.full_text.as_deref()?.strip_prefix("RT @")?;
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        Some(&rest[..end]).filter(|username| !username.is_empty())
+    }
+
+    /// Returns whether this tweet quotes another tweet.
+    fn is_quote(&self) -> bool {
+        self.quoted_status_id_str.is_some() || self.quoted_status_permalink.is_some()
+    }
+
+    /// Returns the username of the account whose tweet this tweet quotes, taken from the link to
+    /// the quoted tweet.
+    fn quoted_username(&self) -> Option<&str> {
+        let url = &self.quoted_status_permalink.as_ref()?.expanded;
+        let path = url.split_once("://").map_or(url.as_str(), |(_, rest)| rest);
+        let mut segments = path.split('/').skip(1);
+        match (segments.next(), segments.next()) {
+            (Some(username), Some("status")) if !username.is_empty() => Some(username),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the tweet has a photo, video, or GIF attached with the given media type.
+    fn has_media(&self, kind: &str) -> bool {
+        self.extended_entities
+            .iter()
+            .flat_map(|e| &e.media)
+            .any(|m| m.kind.as_deref() == Some(kind))
+    }
+
+    /// Returns whether the tweet has no media attached.
+    fn has_no_media(&self) -> bool {
+        self.extended_entities
+            .as_ref()
+            .is_none_or(|e| e.media.is_empty())
+    }
+
+    /// Returns the length of the tweet's text in characters (Unicode scalar values, so an emoji
+    /// counts once), either all of it or only the displayed part.
+    fn length(&self, mode: LengthMode) -> Option<usize> {
+        let text = self.full_text.as_deref()?;
+        let length = text.chars().count();
+        Some(match (mode, self.display_text_range) {
+            (LengthMode::Display, Some((start, end))) => end.min(length).saturating_sub(start),
+            _ => length,
+        })
+    }
+
+    /// Returns when the tweet was posted, falling back to the time encoded in its ID for
+    /// archives that don't record it or record it in an unknown format.
+    fn created_at(&self) -> Option<DateTime<Utc>> {
+        match &self.created_at {
+            Some(Ok(created_at)) => Some(*created_at),
+            _ => snowflake_to_datetime(&self.id),
+        }
+    }
+
+    /// Returns the display name of the client the tweet was posted from, taken from the text of
+    /// the `<a href="...">Name</a>` link in `source`.
+    fn client(&self) -> Option<&str> {
+        let source = self.source.as_deref()?;
+        let name = source
+            .split_once('>')
+            .and_then(|(_, rest)| rest.rsplit_once("</a>"))
+            .map_or(source, |(name, _)| name);
+        Some(name.trim())
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct WrappedLike {
+    like: Like,
+    /// The entry exactly as it appears in the archive, including fields the tool doesn't use.
+    #[serde(skip)]
+    raw: Value,
+}
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Like {
+    tweet_id: String,
+    full_text: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct WrappedConversation {
+    #[serde(rename = "dmConversation")]
+    dm_conversation: Conversation,
+}
+#[derive(Deserialize, Debug)]
+struct Conversation {
+    messages: Vec<ConversationEvent>,
+}
+#[derive(Deserialize, Debug)]
+struct ConversationEvent {
+    /// Only set for actual messages, conversations also contain events like people joining.
+    #[serde(rename = "messageCreate")]
+    message_create: Option<Value>,
+}
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct DirectMessage {
+    id: String,
+    sender_id: String,
+    created_at: DateTime<Utc>,
+    text: Option<String>,
+    /// The message exactly as it appears in the archive, including fields the tool doesn't use.
+    #[serde(skip)]
+    raw: Value,
+}
+
+/// An account that was blocked or muted, from `data/block.js` or `data/mute.js`.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct RelatedAccount {
+    account_id: String,
+    user_link: Option<String>,
+    /// The entry exactly as it appears in the archive, including fields the tool doesn't use.
+    #[serde(skip)]
+    raw: Value,
+}
+
+#[derive(Deserialize, Debug)]
+struct WrappedAccount {
+    account: Account,
+}
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Account {
+    username: String,
+    account_id: String,
+}
+
+/// An item from the archive that the tool will act on.
+struct Target {
+    id: String,
+    created_at: Option<DateTime<Utc>>,
+    /// The original tweet, if this is a retweet that should be undone with an unretweet.
+    retweeted_status_id: Option<String>,
+    /// The account that sent this item, for direct messages.
+    sender_id: Option<String>,
+    /// The text of this item, if the archive has it.
+    text: Option<String>,
+    /// The item exactly as it appears in the archive.
+    raw: Value,
+    /// The URLs of the media attached to this item.
+    media_urls: Vec<String>,
+    /// The redacted copy to post before this tweet is deleted, with `--redact`.
+    repost: Option<Repost>,
+    /// The account this tweet replies to, if it's a reply.
+    in_reply_to_user_id: Option<String>,
+    /// The handle of the account whose tweet this one quotes, if it's a quote tweet.
+    quoted_username: Option<String>,
+}
+
+impl Target {
+    /// Returns whether the IDs needed to act on this item are numbers, as twitter's API expects.
+    /// A corrupt archive, or one with an `id` that isn't the tweet's `id_str`, might have others.
+    fn has_numeric_ids(&self) -> bool {
+        self.id.parse::<u64>().is_ok()
+            && self
+                .retweeted_status_id
+                .as_ref()
+                .is_none_or(|id| id.parse::<u64>().is_ok())
+    }
+}
+
+impl From<WrappedTweet> for Target {
+    fn from(t: WrappedTweet) -> Self {
+        let media_urls = t
+            .tweet
+            .extended_entities
+            .iter()
+            .flat_map(|e| &e.media)
+            .map(Media::best_url)
+            .collect();
+        let quoted_username = t.tweet.quoted_username().map(str::to_string);
+        Target {
+            created_at: t.tweet.created_at(),
+            id: t.tweet.id,
+            retweeted_status_id: t.tweet.retweeted_status_id,
+            sender_id: None,
+            text: t.tweet.full_text,
+            in_reply_to_user_id: t.tweet.in_reply_to_user_id,
+            quoted_username,
+            raw: t.raw,
+            media_urls,
+            repost: None,
+        }
+    }
+}
+
+impl From<WrappedLike> for Target {
+    fn from(l: WrappedLike) -> Self {
+        Target {
+            // The archive doesn't record when a like happened, so this is when the liked tweet was
+            // posted.
+            created_at: snowflake_to_datetime(&l.like.tweet_id),
+            id: l.like.tweet_id,
+            retweeted_status_id: None,
+            sender_id: None,
+            text: l.like.full_text,
+            raw: l.raw,
+            media_urls: Vec::new(),
+            repost: None,
+            in_reply_to_user_id: None,
+            quoted_username: None,
+        }
+    }
+}
+
+impl From<DirectMessage> for Target {
+    fn from(m: DirectMessage) -> Self {
+        Target {
+            id: m.id,
+            created_at: Some(m.created_at),
+            retweeted_status_id: None,
+            sender_id: Some(m.sender_id),
+            text: m.text,
+            raw: m.raw,
+            media_urls: Vec::new(),
+            repost: None,
+            in_reply_to_user_id: None,
+            quoted_username: None,
+        }
+    }
+}
+
+impl From<RelatedAccount> for Target {
+    fn from(a: RelatedAccount) -> Self {
+        Target {
+            id: a.account_id,
+            // The archive doesn't record when an account was blocked or muted.
+            created_at: None,
+            retweeted_status_id: None,
+            sender_id: None,
+            text: a.user_link,
+            raw: a.raw,
+            media_urls: Vec::new(),
+            repost: None,
+            in_reply_to_user_id: None,
+            quoted_username: None,
+        }
+    }
+}
+
+/// A `created_at` timestamp in none of the formats the tool knows.
+#[derive(Debug, Clone, PartialEq)]
+struct InvalidTimestamp(String);
+
+impl std::fmt::Display for InvalidTimestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unrecognized timestamp {:?}", self.0)
+    }
+}
+
+/// The `created_at` formats that include a UTC offset. Twitter archives use the first, while
+/// some older or localized ones use ISO 8601.
+const TIMESTAMP_FORMATS: &[&str] = &[
+    "%a %b %d %H:%M:%S %z %Y",
+    "%Y-%m-%dT%H:%M:%S%.f%:z",
+    "%Y-%m-%dT%H:%M:%S%.f%z",
+    "%Y-%m-%d %H:%M:%S%.f %z",
+];
+
+/// The ISO 8601 `created_at` formats without a UTC offset, which are taken to be in UTC.
+const NAIVE_TIMESTAMP_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"];
+
+/// Parses a `created_at` timestamp in any of the formats archives are known to use.
+fn parse_created_at(created_at: &str) -> Result<DateTime<Utc>, InvalidTimestamp> {
+    let created_at = created_at.trim();
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(created_at) {
+        return Ok(parsed.with_timezone(&Utc));
+    }
+    TIMESTAMP_FORMATS
+        .iter()
+        .find_map(|format| DateTime::parse_from_str(created_at, format).ok())
+        .map(|parsed| parsed.with_timezone(&Utc))
+        .or_else(|| {
+            NAIVE_TIMESTAMP_FORMATS.iter().find_map(|format| {
+                NaiveDateTime::parse_from_str(created_at, format)
+                    .ok()
+                    .map(|parsed| Utc.from_utc_datetime(&parsed))
+            })
+        })
+        .ok_or_else(|| InvalidTimestamp(created_at.to_string()))
+}
+
+/// Parses the `created_at` timestamp of an archived tweet. A timestamp that can't be parsed is
+/// kept as an error rather than failing the whole archive, so the tweet can fall back to the time
+/// encoded in its ID.
+fn deserialize_created_at<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Result<DateTime<Utc>, InvalidTimestamp>>, D::Error> {
+    Ok(
+        Option::<String>::deserialize(deserializer)?
+            .map(|created_at| parse_created_at(&created_at)),
+    )
+}
+
+/// The millisecond epoch twitter's Snowflake IDs count from, in Unix time.
+const SNOWFLAKE_EPOCH_MS: i64 = 1_288_834_974_657;
+
+/// The first Snowflake ID. Older tweets have sequential IDs that don't encode a time.
+const FIRST_SNOWFLAKE: u64 = 29_700_859_247;
+
+/// Decodes the creation time embedded in a tweet's Snowflake ID, to the millisecond.
+fn snowflake_to_datetime(id: &str) -> Option<DateTime<Utc>> {
+    let id = id.parse::<u64>().ok().filter(|&id| id >= FIRST_SNOWFLAKE)?;
+    let ms = (id >> 22) as i64 + SNOWFLAKE_EPOCH_MS;
+    Utc.timestamp_millis_opt(ms).single()
+}
+
+/// Parses an engagement count, which archives store as a string.
+fn deserialize_count<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Count {
+        Number(u64),
+        String(String),
+    }
+    match Count::deserialize(deserializer)? {
+        Count::Number(count) => Ok(count),
+        Count::String(count) => count.trim().parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// Parses a tweet ID, which archives store as a string but some tools write as a number.
+fn deserialize_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Id {
+        Number(u64),
+        String(String),
+    }
+    Ok(match Id::deserialize(deserializer)? {
+        Id::Number(id) => id.to_string(),
+        Id::String(id) => id,
+    })
+}
+
+/// Parses a `[start, end]` pair of character offsets, which archives store as strings.
+fn deserialize_range<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<(usize, usize)>, D::Error> {
+    #[derive(Deserialize)]
+    struct Offset(#[serde(deserialize_with = "deserialize_count")] u64);
+    let range = Option::<(Offset, Offset)>::deserialize(deserializer)?;
+    Ok(range.map(|(start, end)| (start.0 as usize, end.0 as usize)))
+}
+
+/// A range of hours of the day, from `start` up to but not including `end`, which wraps around
+/// midnight when `end` is before `start`.
+#[derive(Debug, Clone, Copy)]
+struct HourRange {
+    start: u32,
+    end: u32,
+}
+
+impl HourRange {
+    fn contains(self, hour: u32) -> bool {
+        if self.start < self.end {
+            self.start <= hour && hour < self.end
+        } else {
+            hour >= self.start || hour < self.end
+        }
+    }
+}
+
+impl std::fmt::Display for HourRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:02}-{:02}", self.start, self.end)
+    }
+}
+
+impl std::str::FromStr for HourRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| "expected a range of hours like 22-04".to_string())?;
+        let parse_hour = |hour: &str, max| match hour.trim().parse::<u32>() {
+            Ok(hour) if hour <= max => Ok(hour),
+            _ => Err(format!("{:?} is not an hour between 0 and {}", hour, max)),
+        };
+        let range = HourRange {
+            start: parse_hour(start, 23)?,
+            end: parse_hour(end, 24)? % 24,
+        };
+        if range.start == range.end {
+            return Err("the start and end hours must be different".to_string());
+        }
+        Ok(range)
+    }
+}
+
+/// A `--redact` rule: text matching the pattern is replaced, with `$1`-style references to the
+/// pattern's capture groups.
+#[derive(Clone, Debug)]
+struct Redaction {
+    pattern: Regex,
+    replacement: String,
+}
+
+impl std::str::FromStr for Redaction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (pattern, replacement) = s.split_once('=').ok_or_else(|| {
+            "expected a pattern and its replacement like REGEX=REPLACEMENT".to_string()
+        })?;
+        if pattern.is_empty() {
+            return Err("the pattern can't be empty".to_string());
+        }
+        let pattern = Regex::new(pattern).map_err(|e| format!("invalid pattern: {}", e))?;
+        Ok(Redaction {
+            pattern,
+            replacement: replacement.to_string(),
+        })
+    }
+}
+
+/// The copy of a tweet that's posted in its place by `--redact`.
+struct Repost {
+    text: String,
+    /// The tweet the original replied to, which the copy replies to as well.
+    in_reply_to_status_id: Option<String>,
+}
+
+impl Repost {
+    /// Applies the rules to the text of a tweet, returning the copy to post if any of them
+    /// changed it. Retweets have no text of their own, so they're never redacted. Only the
+    /// displayed part of a reply is kept, twitter adds the @mentions back.
+    fn redact(tweet: &Tweet, rules: &[Redaction]) -> Option<Repost> {
+        if rules.is_empty() || tweet.is_retweet() {
+            return None;
+        }
+        let text = tweet.full_text.as_deref()?;
+        let text = match (
+            tweet.in_reply_to_status_id.as_ref(),
+            tweet.display_text_range,
+        ) {
+            (Some(_), Some((start, end))) => text.chars().take(end).skip(start).collect(),
+            _ => text.to_string(),
+        };
+        let text = unescape_html(&text);
+        let redacted = rules.iter().fold(text.clone(), |text, rule| {
+            rule.pattern
+                .replace_all(&text, rule.replacement.as_str())
+                .into_owned()
+        });
+        if redacted == text {
+            return None;
+        }
+        Some(Repost {
+            text: redacted.trim().to_string(),
+            in_reply_to_status_id: tweet.in_reply_to_status_id.clone(),
+        })
+    }
+}
+
+/// What the tool removes from the account.
+#[derive(ArgEnum, Clone, Copy, PartialEq)]
+enum Mode {
+    /// Delete the tweets in `data/tweet.js`
+    Tweets,
+    /// Unlike the tweets in `data/like.js`
+    Likes,
+    /// Delete the direct messages in `data/direct-messages.js`
+    Dms,
+    /// Unblock the accounts in `data/block.js`
+    Unblock,
+    /// Unmute the accounts in `data/mute.js`
+    Unmute,
+    /// Delete the tweets on the account's timeline, read from twitter instead of an archive
+    Timeline,
+}
+
+impl Mode {
+    /// The items this mode acts on, as used in messages.
+    fn noun(self) -> &'static str {
+        match self {
+            Mode::Tweets | Mode::Likes | Mode::Timeline => "tweets",
+            Mode::Dms => "messages",
+            Mode::Unblock | Mode::Unmute => "accounts",
+        }
+    }
+
+    /// The action this mode performs, as used in messages.
+    fn verb(self) -> &'static str {
+        match self {
+            Mode::Tweets | Mode::Dms | Mode::Timeline => "delete",
+            Mode::Likes => "unlike",
+            Mode::Unblock => "unblock",
+            Mode::Unmute => "unmute",
+        }
+    }
+
+    /// The past tense of [`Mode::verb`], capitalized for the start of a message.
+    fn past_tense(self) -> &'static str {
+        match self {
+            Mode::Tweets | Mode::Dms | Mode::Timeline => "Deleted",
+            Mode::Likes => "Unliked",
+            Mode::Unblock => "Unblocked",
+            Mode::Unmute => "Unmuted",
+        }
+    }
+
+    /// Returns whether this mode removes something that can't be brought back. Blocks and mutes
+    /// can simply be added again.
+    fn is_permanent(self) -> bool {
+        !matches!(self, Mode::Unblock | Mode::Unmute)
+    }
+}
+
+/// What part of a tweet's text counts towards --min-length and --max-length.
+#[derive(ArgEnum, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+enum LengthMode {
+    /// All of the text, including the @mentions at the start of a reply and media links
+    #[default]
+    Raw,
+    /// Only the text shown as the body of the tweet, for archives that record it
+    Display,
+}
+
+/// The order in which tweets are deleted.
+#[derive(ArgEnum, Clone, Copy)]
+enum Order {
+    Oldest,
+    Newest,
+}
+
+/// How --sample-fraction picks the tweets to delete.
+#[derive(ArgEnum, Clone, Copy, PartialEq)]
+enum SampleStrategy {
+    /// Every tweet is as likely to be picked as any other
+    Uniform,
+    /// Every month of the account's history loses the same share of its tweets
+    AgeWeighted,
+}
+
+/// How the progress of a run is shown.
+#[derive(ArgEnum, Clone, Copy, PartialEq)]
+enum Progress {
+    /// A progress bar that redraws itself in place
+    Bar,
+    /// A line of plain text every few seconds, for logs
+    Plain,
+    /// No progress, only the summary at the end
+    None,
+}
+
+/// What has to be typed to confirm a run, instead of answering y/N.
+#[derive(ArgEnum, Clone, Copy, PartialEq)]
+enum ConfirmPhrase {
+    /// The @handle of the account
+    Handle,
+    /// The number of items about to be deleted
+    Count,
+}
+
+/// The outcome recorded for a tweet in the deletion log.
+#[derive(ArgEnum, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[clap(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+enum Status {
+    Deleted,
+    AlreadyDeleted,
+    Failed,
+    DryRun,
+}
+
+/// A single line of the deletion log.
+#[derive(Serialize)]
+struct LogEntry<'a> {
+    id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_at: Option<DateTime<Utc>>,
+    status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// The full text, recorded for dry runs so the log doubles as a preview.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<&'a str>,
+}
+
+/// A line of a deletion log read back by --diff-against or --replay.
+#[derive(Deserialize)]
+struct Logged {
+    id: String,
+    status: Option<Status>,
+}
+
+/// Reads every line of a deletion log written by --log-file, in order.
+fn read_log(path: &Path) -> Result<Vec<Logged>, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            serde_json::from_str::<Logged>(line).map_err(|e| format!("line {}: {}", number + 1, e))
+        })
+        .collect()
+}
+
+/// Reads the IDs of every tweet recorded in a deletion log written by --log-file.
+fn read_log_ids(path: &Path) -> Result<HashSet<String>, String> {
+    Ok(read_log(path)?
+        .into_iter()
+        .map(|logged| logged.id)
+        .collect())
+}
+
+/// Reads the IDs of the tweets in a deletion log that were last recorded with one of the
+/// statuses. A tweet that's logged more than once, like one that failed in one run and was
+/// deleted by the next in logs joined together, only counts with its latest status.
+fn read_replay_ids(path: &Path, statuses: &[Status]) -> Result<HashSet<String>, String> {
+    let mut latest = HashMap::new();
+    for logged in read_log(path)? {
+        latest.insert(logged.id, logged.status);
+    }
+    Ok(latest
+        .into_iter()
+        .filter(|(_, status)| status.is_some_and(|status| statuses.contains(&status)))
+        .map(|(id, _)| id)
+        .collect())
+}
+
+/// How many IDs are listed in a warning before the rest are only counted.
+const LISTED_IDS: usize = 10;
+
+/// Formats IDs for a warning, quoting them so empty or blank ones show up.
+fn list_ids<'a>(ids: impl ExactSizeIterator<Item = &'a str>) -> String {
+    let total = ids.len();
+    let mut listed = ids
+        .take(LISTED_IDS)
+        .map(|id| format!("{:?}", id))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if total > LISTED_IDS {
+        listed.push_str(&format!(" and {} more", total - LISTED_IDS));
+    }
+    listed
+}
+
+/// Sorts tweet IDs from oldest to newest.
+fn sorted_ids<'a>(ids: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut ids = ids.into_iter().collect::<Vec<_>>();
+    ids.sort_by_key(|id| (id.parse::<u64>().ok(), *id));
+    ids
+}
+
+/// Prints how the tweets about to be processed differ from the ones in a previous run's log.
+fn print_diff(targets: &[Target], previous: &HashSet<String>, path: &Path, verbose: bool) {
+    let current = targets
+        .iter()
+        .map(|t| t.id.as_str())
+        .collect::<HashSet<_>>();
+    let added = sorted_ids(current.iter().copied().filter(|id| !previous.contains(*id)));
+    let removed = sorted_ids(
+        previous
+            .iter()
+            .map(String::as_str)
+            .filter(|id| !current.contains(id)),
+    );
+    info!(
+        "🔀 Compared to {}: +{} / -{}",
+        path.display(),
+        added.len(),
+        removed.len()
+    );
+    if verbose {
+        for id in added {
+            info!("  + {}", id);
+        }
+        for id in removed {
+            info!("  - {}", id);
+        }
+    }
+}
+
+/// Returns how many terminal columns a grapheme cluster takes up. Emoji sequences joined into a
+/// single symbol are counted as one wide character, instead of one per emoji in the sequence.
+fn grapheme_width(grapheme: &str) -> usize {
+    grapheme.width().min(2)
+}
+
+/// Shortens text to fit in `width` terminal columns on a single line, marking it with an ellipsis
+/// if it was cut off. Newlines and runs of whitespace are collapsed into single spaces, and the
+/// text is only ever cut between grapheme clusters, so emoji and accented letters stay whole.
+fn truncate_display(text: &str, width: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.graphemes(true).map(grapheme_width).sum::<usize>() <= width {
+        return text;
+    }
+    let mut used = 0;
+    let mut truncated = String::new();
+    for grapheme in text.graphemes(true) {
+        used += grapheme_width(grapheme);
+        // Leave a column for the ellipsis.
+        if used >= width {
+            break;
+        }
+        truncated.push_str(grapheme);
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// Tweets that were successfully processed, persisted so an interrupted run can be resumed.
+#[derive(Serialize, Deserialize, Default)]
+struct State {
+    processed: HashSet<String>,
+    /// How many tweets have been recorded since the state file was last written.
+    #[serde(skip)]
+    unsaved: usize,
+}
+
+impl State {
+    /// Records a processed tweet, returning whether enough have been recorded since the last
+    /// checkpoint that the state file should be written again.
+    fn record(&mut self, id: &str, checkpoint_every: usize) -> bool {
+        if !self.processed.insert(id.to_string()) {
+            return false;
+        }
+        self.unsaved += 1;
+        if self.unsaved < checkpoint_every {
+            return false;
+        }
+        self.unsaved = 0;
+        true
+    }
+}
+
+/// Everything needed to carry on a cleanup on another machine, written by --export-state and
+/// read back by --import-state.
+#[derive(Serialize, Deserialize, Default)]
+struct StateBundle {
+    /// The account the items were removed from, so the bundle isn't used with another
+    /// account's archive.
+    account_id: String,
+    /// The items that were removed, or found to be gone already, like in the state file.
+    processed: HashSet<String>,
+    /// The items that failed to be removed and haven't been removed since.
+    failed_ids: HashSet<String>,
+    /// Totals across every run the bundle was carried through.
+    runs: usize,
+    deleted: usize,
+    already_deleted: usize,
+    failed: usize,
+}
+
+impl StateBundle {
+    /// Adds a finished run.
+    fn record(&mut self, summary: &Summary) {
+        self.runs += 1;
+        self.deleted += summary.deleted - summary.already_deleted;
+        self.already_deleted += summary.already_deleted;
+        self.failed += summary.failed;
+        for id in &summary.removed_ids {
+            self.failed_ids.remove(id);
+        }
+        self.processed.extend(summary.removed_ids.iter().cloned());
+        self.failed_ids.extend(summary.failed_ids.iter().cloned());
+    }
+}
+
+/// Access token saved to disk after a successful PIN login, so later runs can skip it.
+#[derive(Serialize, Deserialize)]
+struct CachedToken {
+    consumer_key: String,
+    access_token: String,
+    access_token_secret: String,
+}
+
+/// Defaults read from the config file. Every field is optional, and anything given on the command
+/// line (or through the environment) takes precedence.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    consumer_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    consumer_secret: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    access_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    access_token_secret: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tasks: Option<usize>,
+    replies_only: bool,
+    top_level_only: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    include_replies_to: Option<String>,
+    self_replies_only: bool,
+    exclude_self_replies: bool,
+    retweets_only: bool,
+    exclude_retweets: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    keep_retweets_of: Vec<String>,
+    quotes_only: bool,
+    exclude_quotes: bool,
+    self_quotes_only: bool,
+    has_photo: bool,
+    has_video: bool,
+    has_gif: bool,
+    no_media: bool,
+    has_mentions: bool,
+    no_mentions: bool,
+    has_hashtags: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    hashtag: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    before: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    after: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    older_than_days: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    newer_than_days: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    since_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_id: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    year: Vec<i32>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_display",
+        deserialize_with = "deserialize_parsed"
+    )]
+    between_hours: Option<HourRange>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_display",
+        deserialize_with = "deserialize_parsed"
+    )]
+    timezone: Option<Tz>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    contains: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    exclude_contains: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    match_regex: Option<String>,
+    ignore_case: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_length: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_length: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    length_mode: Option<LengthMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_favorites: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_retweets: Option<u64>,
+    delete_popular: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    from_client: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exclude_client: Option<String>,
+    invert: bool,
+    keep_self_threads: bool,
+    dedupe_threads: bool,
+    delete_pinned: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_first: Option<usize>,
+    preserve_media_tweets: bool,
+    report_orphans: bool,
+    keep_orphans: bool,
+    orphans_only: bool,
+}
+
+/// Writes an option of the config file the way it's given on the command line.
+fn serialize_display<S: Serializer, T: std::fmt::Display>(
+    value: &Option<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => serializer.collect_str(value),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Reads an option of the config file that's written the way it's given on the command line.
+fn deserialize_parsed<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| value.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// Damae is a tool for erasing all tweets from a twitter account.
+#[derive(Parser)]
+#[clap(
+    version = "1.0",
+    author = "Rachel",
+    setting = AppSettings::SubcommandsNegateReqs,
+    setting = AppSettings::ArgsNegateSubcommands,
+    after_help = "EXIT STATUS:
+    0    Every selected tweet was deleted
+    1    The run could not start, e.g. because of bad options, archive, or credentials
+    2    The run finished, but some tweets failed to be deleted"
+)]
+struct Cli {
+    #[clap(subcommand)]
+    command: Option<Command>,
+    /// Running without a subcommand, with --mode choosing what to delete, is deprecated
+    #[clap(flatten)]
+    legacy: Opts,
+}
+
+// Only one command is ever built, when parsing the arguments, so the variants' sizes don't matter.
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand)]
+enum Command {
+    /// Delete tweets and undo retweets
+    Tweets {
+        #[clap(flatten)]
+        common: CommonOpts,
+        #[clap(flatten)]
+        tweets: TweetOpts,
+    },
+    /// Unlike liked tweets
+    Likes {
+        #[clap(flatten)]
+        common: CommonOpts,
+        #[clap(flatten)]
+        likes: LikeOpts,
+    },
+    /// Delete sent direct messages
+    Dms {
+        #[clap(flatten)]
+        common: CommonOpts,
+    },
+    /// Unblock blocked accounts
+    Unblock {
+        #[clap(flatten)]
+        common: CommonOpts,
+    },
+    /// Unmute muted accounts
+    Unmute {
+        #[clap(flatten)]
+        common: CommonOpts,
+    },
+    /// Delete tweets and undo retweets from your timeline, without an archive (only reaches your
+    /// latest 3200 tweets)
+    Timeline {
+        #[clap(flatten)]
+        common: CommonOpts,
+        #[clap(flatten)]
+        tweets: TweetOpts,
+    },
+    /// Print how much has been deleted across every run, and exit
+    Stats,
+    /// Print every option that chooses which tweets `damae tweets` deletes, with examples, and
+    /// exit
+    HelpFilters,
+}
+
+/// Everything that controls a run, however it was given on the command line. Only the options
+/// for the selected mode are set, the others keep their defaults.
+#[derive(Args, Clone)]
+struct Opts {
+    /// Whether to delete tweets, unlike liked tweets, delete sent direct messages, unblock or
+    /// unmute accounts, or delete the tweets on your timeline, when running without a subcommand
+    /// (deprecated, use the subcommand instead)
+    #[clap(arg_enum, long = "mode", default_value = "tweets")]
+    mode: Mode,
+    #[clap(flatten)]
+    common: CommonOpts,
+    #[clap(flatten)]
+    tweets: TweetOpts,
+    #[clap(flatten)]
+    likes: LikeOpts,
+}
+
+impl Command {
+    /// Returns the options for a run, or `None` for commands that don't delete anything.
+    fn into_opts(self) -> Option<Opts> {
+        Some(match self {
+            Command::Tweets { common, tweets } => Opts {
+                mode: Mode::Tweets,
+                common,
+                tweets,
+                likes: LikeOpts::default(),
+            },
+            Command::Likes { common, likes } => Opts {
+                mode: Mode::Likes,
+                common,
+                tweets: TweetOpts::default(),
+                likes,
+            },
+            Command::Dms { common } => Opts {
+                mode: Mode::Dms,
+                common,
+                tweets: TweetOpts::default(),
+                likes: LikeOpts::default(),
+            },
+            Command::Unblock { common } => Opts {
+                mode: Mode::Unblock,
+                common,
+                tweets: TweetOpts::default(),
+                likes: LikeOpts::default(),
+            },
+            Command::Unmute { common } => Opts {
+                mode: Mode::Unmute,
+                common,
+                tweets: TweetOpts::default(),
+                likes: LikeOpts::default(),
+            },
+            Command::Timeline { common, tweets } => Opts {
+                mode: Mode::Timeline,
+                common,
+                tweets,
+                likes: LikeOpts::default(),
+            },
+            Command::Stats | Command::HelpFilters => return None,
+        })
+    }
+}
+
+/// Options shared by every mode: the archive, credentials, and how the run is carried out.
+#[derive(Args, Clone)]
+struct CommonOpts {
+    /// Path to the twitter archive, either unzipped or as a .zip file, or an http(s):// or s3://
+    /// URL to download it from (not used by `damae timeline`)
+    archive_path: Option<String>,
+    /// Consumer key for the twitter API
+    #[clap(
+        long = "consumer-key",
+        env = "DAMAE_CONSUMER_KEY",
+        hide_env_values = true
+    )]
+    consumer_key: Option<String>,
+    /// Consumer secret for the twitter API
+    #[clap(
+        long = "consumer-secret",
+        env = "DAMAE_CONSUMER_SECRET",
+        hide_env_values = true
+    )]
+    consumer_secret: Option<String>,
+    /// Access token for the twitter API
+    #[clap(
+        long = "access-token",
+        env = "DAMAE_ACCESS_TOKEN",
+        hide_env_values = true
+    )]
+    access_token: Option<String>,
+    /// Access token secret for the twitter API
+    #[clap(
+        long = "access-token-secret",
+        env = "DAMAE_ACCESS_TOKEN_SECRET",
+        hide_env_values = true
+    )]
+    access_token_secret: Option<String>,
+    /// If enabled, log in with OAuth 2.0 in the browser instead of the PIN flow. This needs a
+    /// client ID, and http://127.0.0.1:<redirect port>/callback registered as a callback URL
+    #[clap(long = "oauth2")]
+    oauth2: bool,
+    /// OAuth 2.0 client ID for the twitter API, used with --oauth2
+    #[clap(long = "client-id", env = "DAMAE_CLIENT_ID", hide_env_values = true)]
+    client_id: Option<String>,
+    /// OAuth 2.0 client secret for the twitter API, only needed for confidential clients
+    #[clap(
+        long = "client-secret",
+        env = "DAMAE_CLIENT_SECRET",
+        hide_env_values = true
+    )]
+    client_secret: Option<String>,
+    /// Local port the browser is redirected to after authorizing with --oauth2
+    #[clap(long = "redirect-port", default_value = "8080")]
+    redirect_port: u16,
+    /// If enabled, the tool will avoid actually executing the delete operations
+    #[clap(long = "dry-run")]
+    dry_run: bool,
+    /// If enabled, the tool will only print how many tweets would be deleted from each year
+    #[clap(long = "summary-only")]
+    summary_only: bool,
+    #[clap(long = "keep-ids", about = filters::help("keep-ids"))]
+    keep_ids: Option<PathBuf>,
+    #[clap(
+        long = "keep-id",
+        about = filters::help("keep-id"),
+        multiple_occurrences = true,
+        number_of_values = 1
+    )]
+    keep_id: Vec<u64>,
+    #[clap(long = "only-ids", about = filters::help("only-ids"))]
+    only_ids: Option<PathBuf>,
+    #[clap(
+        long = "id",
+        about = filters::help("id"),
+        multiple_occurrences = true,
+        number_of_values = 1
+    )]
+    id: Vec<u64>,
+    #[clap(long = "ids-from-stdin", about = filters::help("ids-from-stdin"))]
+    ids_from_stdin: bool,
+    #[clap(long = "limit", about = filters::help("limit"))]
+    limit: Option<usize>,
+    /// If enabled, the tool will delete tweets from oldest to newest, or newest to oldest
+    #[clap(arg_enum, long = "order")]
+    order: Option<Order>,
+    /// If enabled, the tool will delete tweets in a random order (with --limit, this deletes a
+    /// random sample)
+    #[clap(long = "shuffle", conflicts_with = "order")]
+    shuffle: bool,
+    #[clap(
+        long = "sample-fraction",
+        about = filters::help("sample-fraction"),
+        parse(try_from_str = parse_fraction)
+    )]
+    sample_fraction: Option<f64>,
+    #[clap(
+        arg_enum,
+        long = "sample-strategy",
+        about = filters::help("sample-strategy"),
+        requires = "sample-fraction"
+    )]
+    sample_strategy: Option<SampleStrategy>,
+    /// Seed for --shuffle and --sample-fraction, so the same tweets are picked in the same order
+    /// on every run
+    #[clap(long = "seed")]
+    seed: Option<u64>,
+    /// Maxiumum number of concurrent deletion tasks
+    #[clap(
+        long = "max-tasks",
+        default_value = "10",
+        parse(try_from_str = parse_max_tasks)
+    )]
+    max_tasks: usize,
+    /// If enabled, the tool will start with a few concurrent deletions and adjust how many run at
+    /// once (up to --max-tasks) based on twitter's rate limits and errors
+    #[clap(long = "concurrency-auto")]
+    concurrency_auto: bool,
+    /// If enabled, the tool will show each tweet and ask whether to delete it, skip it, or stop
+    /// (implies --max-tasks 1)
+    #[clap(long = "interactive")]
+    interactive: bool,
+    /// If enabled, the tool will neither read nor write the cached access token
+    #[clap(long = "no-cache")]
+    no_cache: bool,
+    /// Path to a TOML file with default values for the credentials, --max-tasks, and the tweet
+    /// filters [default: <config dir>/damae/config.toml]
+    #[clap(long = "config")]
+    config: Option<PathBuf>,
+    /// Print the configuration that results from combining the config file and the command line,
+    /// with secrets redacted, and exit
+    #[clap(long = "print-config")]
+    print_config: bool,
+    /// Delete the cached access token before running, forcing a new authorization
+    #[clap(long)]
+    logout: bool,
+    /// Maximum number of times a failed deletion is retried, for errors that may be transient
+    #[clap(long = "max-retries", default_value = "3")]
+    max_retries: u32,
+    /// If enabled, the tool will not pause when the twitter rate limit is about to run out
+    #[clap(long = "ignore-rate-limit")]
+    ignore_rate_limit: bool,
+    /// If enabled, the tool will make at most this many deletion requests per minute, however
+    /// many tasks are running (0 means no limit)
+    #[clap(long = "rate-limit", default_value = "0")]
+    rate_limit: u32,
+    /// If enabled, the tool will work through the items this many at a time, pausing for
+    /// --batch-delay seconds after each batch
+    #[clap(
+        long = "batch-size",
+        requires = "batch-delay",
+        parse(try_from_str = parse_batch_size)
+    )]
+    batch_size: Option<usize>,
+    /// How many seconds to pause between batches of --batch-size items
+    #[clap(long = "batch-delay", requires = "batch-size")]
+    batch_delay: Option<u64>,
+    /// If enabled, a deletion request that takes longer than this many seconds is abandoned and
+    /// retried like any other transient failure
+    #[clap(long = "request-timeout")]
+    request_timeout: Option<u64>,
+    /// If enabled, the tool will stop starting new deletions after running for this many
+    /// seconds, and print a summary of what it got through
+    #[clap(long = "max-runtime")]
+    max_runtime: Option<u64>,
+    /// Proxy to send every request through, like http://host:port or socks5://host:port, with
+    /// optional user:password@ credentials
+    #[clap(long = "proxy", env = "HTTPS_PROXY")]
+    proxy: Option<net::Proxy>,
+    /// If enabled, tweets that twitter can't find (because they were deleted after the archive
+    /// was exported) count as failures, instead of being reported as already gone
+    #[clap(long = "no-skip-missing")]
+    no_skip_missing: bool,
+    /// If enabled, the tool will stop the whole run as soon as one deletion fails (tweets that
+    /// were already deleted don't count as failures, unless --no-skip-missing is given)
+    #[clap(long = "fail-fast")]
+    fail_fast: bool,
+    /// If enabled, the tool will print a JSON summary of the run to stdout when it finishes
+    /// (all other output goes to stderr)
+    ///
+    /// The summary is a single object: {"considered": <number of tweets selected>, "deleted":
+    /// <number>, "already_deleted": <number>, "failed": <number>, "skipped": <number>,
+    /// "not_processed": <number left when the run was stopped early>, "dry_run": <bool>,
+    /// "elapsed_seconds": <number>, "error_codes": {"<twitter error code>": <number of failures>}}
+    #[clap(long = "json-report")]
+    json_report: bool,
+    /// If enabled, the tool will write a self-contained HTML page with the summary of the run, the
+    /// breakdown by year, and the filters used to the given file when it finishes
+    #[clap(long = "report-html")]
+    report_html: Option<PathBuf>,
+    /// Serve counters of the run's progress in the Prometheus text format on this address while
+    /// it runs, like 127.0.0.1:9090
+    #[clap(long = "metrics-addr", parse(try_from_str = parse_metrics_addr))]
+    metrics_addr: Option<SocketAddr>,
+    /// If enabled, the tool will check with twitter that the deleted tweets are really gone once
+    /// the run finishes
+    #[clap(long = "verify-after")]
+    verify_after: bool,
+    /// Number of randomly chosen deletions to check with --verify-after [default: all of them,
+    /// up to 1000]
+    #[clap(long = "verify-sample", requires = "verify-after")]
+    verify_sample: Option<usize>,
+    /// If enabled, the tool will save the complete archive data for every tweet it's about to
+    /// delete to the given JSON file first (even in dry-run mode)
+    #[clap(long = "backup")]
+    backup: Option<PathBuf>,
+    /// If enabled, the tool will download the photos and videos attached to each tweet into a
+    /// directory named after the tweet's ID within the given directory, before deleting it
+    #[clap(long = "save-media")]
+    save_media: Option<PathBuf>,
+    /// If enabled, the tool will not delete a tweet if saving its media with --save-media fails
+    #[clap(long = "require-media-saved", requires = "save-media")]
+    require_media_saved: bool,
+    /// If enabled, the tool will write the outcome for each tweet to the given file, as one
+    /// JSON object per line
+    #[clap(long = "log-file")]
+    log_file: Option<PathBuf>,
+    /// If enabled, the tool will write the IDs of the tweets that failed to be deleted to the
+    /// given file, one per line, so they can be retried with --only-ids
+    #[clap(long = "save-failed-ids")]
+    save_failed_ids: Option<PathBuf>,
+    /// If enabled, the tool will compare the tweets it's about to delete to the ones in the given
+    /// log file from a previous run (written with --log-file), and print how many were added and
+    /// removed (listing them with --verbose)
+    #[clap(long = "diff-against")]
+    diff_against: Option<PathBuf>,
+    #[clap(long = "replay", about = filters::help("replay"), requires = "status")]
+    replay: Option<PathBuf>,
+    #[clap(
+        arg_enum,
+        long = "status",
+        about = filters::help("status"),
+        requires = "replay",
+        multiple_occurrences = true,
+        number_of_values = 1
+    )]
+    status: Vec<Status>,
+    /// If enabled, the tool will record successfully processed tweets in the given file, and
+    /// skip tweets recorded there by previous runs
+    #[clap(long = "state-file")]
+    state_file: Option<PathBuf>,
+    /// If enabled, the tool will write the tweets processed so far, the ones that failed, and
+    /// the totals of every run to the given file when it finishes, to carry on elsewhere with
+    /// --import-state
+    #[clap(long = "export-state")]
+    export_state: Option<PathBuf>,
+    /// If enabled, the tool will carry on from a file written by --export-state, skipping the
+    /// tweets it records as processed. The file has to be from the same account as the archive
+    #[clap(long = "import-state")]
+    import_state: Option<PathBuf>,
+    /// How many tweets are recorded between writes of the state file, on top of the write when
+    /// the run ends
+    #[clap(long = "checkpoint-every", default_value = "100")]
+    checkpoint_every: usize,
+    /// If enabled, the tool will continue even if the archive belongs to a different account
+    /// than the one that is logged in
+    #[clap(long = "force-account-mismatch")]
+    force_account_mismatch: bool,
+    /// Print more detailed output, including every successful deletion (can be repeated)
+    #[clap(long, short, parse(from_occurrences))]
+    verbose: u8,
+    /// Only print warnings and errors
+    #[clap(long, short, conflicts_with = "verbose")]
+    quiet: bool,
+    /// How many columns of each tweet's text are shown when listing a dry run or the tweets about
+    /// to be deleted
+    #[clap(
+        long = "preview-text-width",
+        default_value = "60",
+        parse(try_from_str = parse_preview_text_width)
+    )]
+    preview_text_width: usize,
+    /// How progress is shown while deleting [default: bar, or plain when the output isn't a
+    /// terminal]
+    #[clap(arg_enum, long = "progress-style")]
+    progress_style: Option<Progress>,
+    /// Show a full-screen dashboard while deleting, with the error codes, the rate, the ETA, and
+    /// the latest failures (falls back to the progress bar if the output isn't a terminal or the
+    /// terminal is too small)
+    #[clap(long = "tui")]
+    tui: bool,
+    /// Confirm the run by typing the account's handle or the number of items, instead of
+    /// answering y/N
+    #[clap(arg_enum, long = "confirm-phrase")]
+    confirm_phrase: Option<ConfirmPhrase>,
+    /// Bypass all confirmation prompts
+    #[clap(long, short)]
+    yes: bool,
+}
+
+/// Options that select which tweets are deleted.
+#[derive(Args, Clone, Default)]
+struct TweetOpts {
+    #[clap(long = "replies-only", about = filters::help("replies-only"))]
+    replies_only: bool,
+    #[clap(long = "top-level-only", about = filters::help("top-level-only"))]
+    top_level_only: bool,
+    #[clap(long = "include-replies-to", about = filters::help("include-replies-to"))]
+    include_replies_to: Option<String>,
+    #[clap(
+        long = "self-replies-only",
+        about = filters::help("self-replies-only"),
+        conflicts_with = "exclude-self-replies"
+    )]
+    self_replies_only: bool,
+    #[clap(long = "exclude-self-replies", about = filters::help("exclude-self-replies"))]
+    exclude_self_replies: bool,
+    #[clap(long = "retweets-only", about = filters::help("retweets-only"))]
+    retweets_only: bool,
+    #[clap(long = "exclude-retweets", about = filters::help("exclude-retweets"))]
+    exclude_retweets: bool,
+    #[clap(
+        long = "keep-retweets-of",
+        about = filters::help("keep-retweets-of"),
+        multiple_occurrences = true,
+        number_of_values = 1
+    )]
+    keep_retweets_of: Vec<String>,
+    #[clap(long = "quotes-only", about = filters::help("quotes-only"))]
+    quotes_only: bool,
+    #[clap(long = "exclude-quotes", about = filters::help("exclude-quotes"))]
+    exclude_quotes: bool,
+    #[clap(long = "self-quotes-only", about = filters::help("self-quotes-only"))]
+    self_quotes_only: bool,
+    #[clap(long = "has-photo", about = filters::help("has-photo"))]
+    has_photo: bool,
+    #[clap(long = "has-video", about = filters::help("has-video"))]
+    has_video: bool,
+    #[clap(long = "has-gif", about = filters::help("has-gif"))]
+    has_gif: bool,
+    #[clap(long = "no-media", about = filters::help("no-media"))]
+    no_media: bool,
+    #[clap(long = "has-mentions", about = filters::help("has-mentions"))]
+    has_mentions: bool,
+    #[clap(long = "no-mentions", about = filters::help("no-mentions"), conflicts_with = "has-mentions")]
+    no_mentions: bool,
+    #[clap(long = "has-hashtags", about = filters::help("has-hashtags"))]
+    has_hashtags: bool,
+    #[clap(long = "hashtag", about = filters::help("hashtag"), multiple_occurrences = true, number_of_values = 1)]
+    hashtag: Vec<String>,
+    #[clap(long = "before", about = filters::help("before"))]
+    older_than: Option<NaiveDate>,
+    #[clap(long = "after", about = filters::help("after"))]
+    newer_than: Option<NaiveDate>,
+    #[clap(long = "older-than-days", about = filters::help("older-than-days"))]
+    older_than_days: Option<u32>,
+    #[clap(long = "newer-than-days", about = filters::help("newer-than-days"))]
+    newer_than_days: Option<u32>,
+    #[clap(long = "since-id", about = filters::help("since-id"))]
+    since_id: Option<u64>,
+    #[clap(long = "max-id", about = filters::help("max-id"))]
+    max_id: Option<u64>,
+    #[clap(long = "year", about = filters::help("year"), multiple_occurrences = true, number_of_values = 1)]
+    year: Vec<i32>,
+    #[clap(long = "between-hours", about = filters::help("between-hours"))]
+    between_hours: Option<HourRange>,
+    #[clap(long = "timezone", about = filters::help("timezone"))]
+    timezone: Option<Tz>,
+    #[clap(long = "contains", about = filters::help("contains"))]
+    contains: Option<String>,
+    #[clap(
+        long = "exclude-contains",
+        about = filters::help("exclude-contains"),
+        multiple_occurrences = true,
+        number_of_values = 1
+    )]
+    exclude_contains: Vec<String>,
+    #[clap(long = "match-regex", about = filters::help("match-regex"))]
+    match_regex: Option<String>,
+    #[clap(long = "ignore-case", about = filters::help("ignore-case"))]
+    ignore_case: bool,
+    #[clap(long = "min-length", about = filters::help("min-length"))]
+    min_length: Option<usize>,
+    #[clap(long = "max-length", about = filters::help("max-length"))]
+    max_length: Option<usize>,
+    #[clap(arg_enum, long = "length-mode", about = filters::help("length-mode"), default_value = "raw")]
+    length_mode: LengthMode,
+    #[clap(long = "min-favorites", about = filters::help("min-favorites"))]
+    min_favorites: Option<u64>,
+    #[clap(long = "min-retweets", about = filters::help("min-retweets"))]
+    min_retweets: Option<u64>,
+    #[clap(long = "delete-popular", about = filters::help("delete-popular"))]
+    delete_popular: bool,
+    #[clap(long = "from-client", about = filters::help("from-client"))]
+    from_client: Option<String>,
+    #[clap(long = "exclude-client", about = filters::help("exclude-client"))]
+    exclude_client: Option<String>,
+    #[clap(long = "invert", about = filters::help("invert"))]
+    invert: bool,
+    #[clap(long = "list-clients", about = filters::help("list-clients"))]
+    list_clients: bool,
+    #[clap(long = "keep-self-threads", about = filters::help("keep-self-threads"))]
+    keep_self_threads: bool,
+    #[clap(long = "dedupe-threads", about = filters::help("dedupe-threads"))]
+    dedupe_threads: bool,
+    #[clap(long = "delete-pinned", about = filters::help("delete-pinned"))]
+    delete_pinned: bool,
+    #[clap(long = "keep-first", about = filters::help("keep-first"), value_name = "N")]
+    keep_first: Option<usize>,
+    #[clap(long = "preserve-media-tweets", about = filters::help("preserve-media-tweets"))]
+    preserve_media_tweets: bool,
+    #[clap(long = "report-orphans", about = filters::help("report-orphans"))]
+    report_orphans: bool,
+    #[clap(
+        long = "keep-orphans",
+        about = filters::help("keep-orphans"),
+        conflicts_with = "orphans-only"
+    )]
+    keep_orphans: bool,
+    #[clap(long = "orphans-only", about = filters::help("orphans-only"))]
+    orphans_only: bool,
+    /// If enabled, the tool will write the tweets it's about to delete to the given directory as
+    /// Markdown files first, one per tweet or self-thread, with their media (even in dry-run mode)
+    #[clap(long = "export-thread-markdown")]
+    export_thread_markdown: Option<PathBuf>,
+    /// If enabled, the tool will write the tweets it's not going to delete to the given file,
+    /// in the format of the archive's data/tweet.js (even in dry-run mode)
+    #[clap(long = "output-remaining")]
+    output_remaining: Option<PathBuf>,
+    /// If enabled, the tool will replace text matching the regular expression before the `=` with
+    /// the text after it (which can refer to capture groups like $1), post the result in place of
+    /// each selected tweet it changes, and then delete the original. Tweets it doesn't change are
+    /// kept. Can be repeated, the rules are applied in order. Needs --enable-redact-repost unless
+    /// --dry-run is set
+    #[clap(
+        long = "redact",
+        value_name = "REGEX=REPLACEMENT",
+        multiple_occurrences = true,
+        number_of_values = 1
+    )]
+    redact: Vec<Redaction>,
+    /// Confirms that --redact should post new tweets, which can't be made to look like the
+    /// originals (they get a new date, and lose their likes, retweets, and media)
+    #[clap(long = "enable-redact-repost")]
+    enable_redact_repost: bool,
+}
+
+/// Options that select which likes are removed.
+#[derive(Args, Clone, Default)]
+struct LikeOpts {
+    /// If enabled, the tool will only unlike tweets that were posted before the given date (in
+    /// the format YYYY-MM-DD). The archive doesn't record when each like happened, so the date
+    /// of the liked tweet is used instead
+    #[clap(long = "delete-likes-older-than")]
+    delete_likes_older_than: Option<NaiveDate>,
+}
+
+/// Sets up logging at the verbosity chosen on the command line, unless `RUST_LOG` overrides it.
+/// Messages are printed without any decoration besides a color for their level.
+fn init_logger(opts: &Opts) {
+    let level = match (opts.common.quiet, opts.common.verbose) {
+        (true, _) => "warn",
+        (false, 0) => "info",
+        (false, 1) => "debug",
+        (false, _) => "trace",
+    };
+    env_logger::Builder::from_env(Env::default().default_filter_or(format!("damae={}", level)))
+        .format(|buf, record| {
+            let mut style = buf.style();
+            match record.level() {
+                Level::Error => style.set_color(Color::Red),
+                Level::Warn => style.set_color(Color::Yellow),
+                Level::Info => style.set_color(Color::Green),
+                Level::Debug | Level::Trace => style.set_color(Color::Cyan),
+            };
+            writeln!(buf, "{}", style.value(record.args()))
+        })
+        .init();
+}
+
+/// Converts a date into the UTC datetime at midnight on that day.
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&date.and_time(NaiveTime::from_hms(0, 0, 0)))
+}
+
+/// The window of creation times an item must fall in to be deleted, combining the absolute and
+/// relative date options.
+#[derive(Debug, Clone, Copy, Default)]
+struct DateRange {
+    /// Items must have been created before this time.
+    before: Option<DateTime<Utc>>,
+    /// Items must have been created after this time.
+    after: Option<DateTime<Utc>>,
+}
+
+impl DateRange {
+    /// Resolves the date options against `now`, which should be captured once per run so every
+    /// comparison uses the same cutoffs. When both an absolute and a relative cutoff are given,
+    /// the stricter one wins.
+    fn from_opts(opts: &Opts, now: DateTime<Utc>) -> Self {
+        let days_ago = |days: u32| now - chrono::Duration::days(days.into());
+        let before = [
+            opts.tweets.older_than.map(start_of_day),
+            opts.tweets.older_than_days.map(days_ago),
+        ];
+        let after = [
+            opts.tweets.newer_than.map(start_of_day),
+            opts.tweets.newer_than_days.map(days_ago),
+        ];
+        DateRange {
+            before: before.iter().flatten().min().copied(),
+            after: after.iter().flatten().max().copied(),
+        }
+    }
+
+    fn contains(self, created_at: DateTime<Utc>) -> bool {
+        self.before.is_none_or(|before| created_at < before)
+            && self.after.is_none_or(|after| created_at > after)
+    }
+
+    /// Returns whether no date options were given.
+    fn is_unbounded(self) -> bool {
+        self.before.is_none() && self.after.is_none()
+    }
+
+    /// Returns whether no time could satisfy both cutoffs.
+    fn is_empty(self) -> bool {
+        matches!((self.before, self.after), (Some(before), Some(after)) if after >= before)
+    }
+}
+
+/// Credentials shorter than this are almost certainly truncated. Real ones are 25 characters or
+/// more, but the check is kept loose so unusual credentials still get through.
+const MIN_CREDENTIAL_LEN: usize = 10;
+
+/// Checks that a credential looks plausible before it's sent anywhere, returning it without any
+/// surrounding whitespace (such as a newline picked up from an environment variable).
+fn check_credential(value: &str) -> Result<&str, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("is empty".to_string());
+    }
+    if let Some(c) = value
+        .chars()
+        .find(|c| c.is_whitespace() || !c.is_ascii_graphic())
+    {
+        return Err(format!("contains an unexpected character {:?}", c));
+    }
+    if value.len() < MIN_CREDENTIAL_LEN {
+        return Err(format!(
+            "is only {} characters long, it may have been cut off",
+            value.len()
+        ));
+    }
+    Ok(value)
+}
+
+/// Returns the location of the access token cache, if a config directory is available.
+fn token_cache_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("damae/token.json"))
+}
+
+/// Returns the location of the OAuth 2.0 token cache, if a config directory is available.
+fn oauth2_token_cache_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("damae/oauth2_token.json"))
+}
+
+/// Returns the location of the lifetime stats file, if a data directory is available.
+fn stats_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("damae/stats.json"))
+}
+
+/// Returns where the config file is read from when --config isn't given.
+fn default_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("damae/config.toml"))
+}
+
+/// Loads the config file, exiting if it can't be read or parsed. A missing file is only an error
+/// if it was chosen explicitly with --config.
+fn load_config(opts: &Opts) -> Config {
+    let path = match (&opts.common.config, default_config_path()) {
+        (Some(path), _) => path.clone(),
+        (None, Some(path)) if path.exists() => path,
+        (None, _) => return Config::default(),
+    };
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            error!("🚨 Could not read config file {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    };
+    match toml::from_str(&contents) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("🚨 Could not parse config file {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Parses --batch-size, since a batch of nothing would never get through the items.
+fn parse_batch_size(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(batch_size) => Ok(batch_size),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Parses --sample-fraction, the share of the tweets to delete.
+fn parse_fraction(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(fraction) if (0.0..=1.0).contains(&fraction) => Ok(fraction),
+        Ok(_) => Err("must be between 0 and 1, like 0.2 for 20%".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Parses --preview-text-width, which needs a column for the ellipsis at the end of cut text.
+fn parse_preview_text_width(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("must be at least 1 column".to_string()),
+        Ok(width) => Ok(width),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Parses --max-tasks, which has to allow at least one deletion at a time or nothing would ever
+/// be deleted.
+fn parse_max_tasks(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("must be at least 1, use a positive number of tasks".to_string()),
+        Ok(max_tasks) => Ok(max_tasks),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_metrics_addr(s: &str) -> Result<SocketAddr, String> {
+    s.to_socket_addrs()
+        .map_err(|e| format!("{}, use HOST:PORT", e))?
+        .next()
+        .ok_or_else(|| format!("{} didn't resolve to an address", s))
+}
+
+/// Fills in every option that wasn't given on the command line from the config file.
+fn apply_config(opts: &mut Opts, config: Config, matches: &clap::ArgMatches) {
+    opts.common.consumer_key = opts.common.consumer_key.take().or(config.consumer_key);
+    opts.common.consumer_secret = opts
+        .common
+        .consumer_secret
+        .take()
+        .or(config.consumer_secret);
+    opts.common.access_token = opts.common.access_token.take().or(config.access_token);
+    opts.common.access_token_secret = opts
+        .common
+        .access_token_secret
+        .take()
+        .or(config.access_token_secret);
+    if let (Some(max_tasks), 0) = (config.max_tasks, matches.occurrences_of("max-tasks")) {
+        opts.common.max_tasks = max_tasks;
+    }
+    opts.tweets.replies_only |= config.replies_only;
+    opts.tweets.top_level_only |= config.top_level_only;
+    opts.tweets.include_replies_to = opts
+        .tweets
+        .include_replies_to
+        .take()
+        .or(config.include_replies_to);
+    opts.tweets.self_replies_only |= config.self_replies_only;
+    opts.tweets.exclude_self_replies |= config.exclude_self_replies;
+    opts.tweets.retweets_only |= config.retweets_only;
+    opts.tweets.exclude_retweets |= config.exclude_retweets;
+    if opts.tweets.keep_retweets_of.is_empty() {
+        opts.tweets.keep_retweets_of = config.keep_retweets_of;
+    }
+    opts.tweets.quotes_only |= config.quotes_only;
+    opts.tweets.exclude_quotes |= config.exclude_quotes;
+    opts.tweets.self_quotes_only |= config.self_quotes_only;
+    opts.tweets.has_photo |= config.has_photo;
+    opts.tweets.has_video |= config.has_video;
+    opts.tweets.has_gif |= config.has_gif;
+    opts.tweets.no_media |= config.no_media;
+    opts.tweets.has_mentions |= config.has_mentions;
+    opts.tweets.no_mentions |= config.no_mentions;
+    opts.tweets.has_hashtags |= config.has_hashtags;
+    if opts.tweets.hashtag.is_empty() {
+        opts.tweets.hashtag = config.hashtag;
+    }
+    opts.tweets.older_than = opts.tweets.older_than.or(config.before);
+    opts.tweets.newer_than = opts.tweets.newer_than.or(config.after);
+    opts.tweets.older_than_days = opts.tweets.older_than_days.or(config.older_than_days);
+    opts.tweets.newer_than_days = opts.tweets.newer_than_days.or(config.newer_than_days);
+    opts.tweets.since_id = opts.tweets.since_id.or(config.since_id);
+    opts.tweets.max_id = opts.tweets.max_id.or(config.max_id);
+    if opts.tweets.year.is_empty() {
+        opts.tweets.year = config.year;
+    }
+    opts.tweets.between_hours = opts.tweets.between_hours.or(config.between_hours);
+    opts.tweets.timezone = opts.tweets.timezone.or(config.timezone);
+    opts.tweets.contains = opts.tweets.contains.take().or(config.contains);
+    if opts.tweets.exclude_contains.is_empty() {
+        opts.tweets.exclude_contains = config.exclude_contains;
+    }
+    opts.tweets.match_regex = opts.tweets.match_regex.take().or(config.match_regex);
+    opts.tweets.ignore_case |= config.ignore_case;
+    opts.tweets.min_length = opts.tweets.min_length.or(config.min_length);
+    opts.tweets.max_length = opts.tweets.max_length.or(config.max_length);
+    if let (Some(length_mode), 0) = (config.length_mode, matches.occurrences_of("length-mode")) {
+        opts.tweets.length_mode = length_mode;
+    }
+    opts.tweets.min_favorites = opts.tweets.min_favorites.or(config.min_favorites);
+    opts.tweets.min_retweets = opts.tweets.min_retweets.or(config.min_retweets);
+    opts.tweets.delete_popular |= config.delete_popular;
+    opts.tweets.from_client = opts.tweets.from_client.take().or(config.from_client);
+    opts.tweets.exclude_client = opts.tweets.exclude_client.take().or(config.exclude_client);
+    opts.tweets.invert |= config.invert;
+    opts.tweets.keep_self_threads |= config.keep_self_threads;
+    opts.tweets.dedupe_threads |= config.dedupe_threads;
+    opts.tweets.delete_pinned |= config.delete_pinned;
+    opts.tweets.keep_first = opts.tweets.keep_first.or(config.keep_first);
+    opts.tweets.preserve_media_tweets |= config.preserve_media_tweets;
+    opts.tweets.report_orphans |= config.report_orphans;
+    opts.tweets.keep_orphans |= config.keep_orphans;
+    opts.tweets.orphans_only |= config.orphans_only;
+}
+
+/// Builds the config file equivalent of the resolved options, with the secrets redacted.
+fn effective_config(opts: &Opts) -> Config {
+    let redact = |secret: &Option<String>| secret.as_ref().map(|_| "<redacted>".to_string());
+    Config {
+        consumer_key: opts.common.consumer_key.clone(),
+        consumer_secret: redact(&opts.common.consumer_secret),
+        access_token: redact(&opts.common.access_token),
+        access_token_secret: redact(&opts.common.access_token_secret),
+        max_tasks: Some(opts.common.max_tasks),
+        replies_only: opts.tweets.replies_only,
+        top_level_only: opts.tweets.top_level_only,
+        include_replies_to: opts.tweets.include_replies_to.clone(),
+        self_replies_only: opts.tweets.self_replies_only,
+        exclude_self_replies: opts.tweets.exclude_self_replies,
+        retweets_only: opts.tweets.retweets_only,
+        exclude_retweets: opts.tweets.exclude_retweets,
+        keep_retweets_of: opts.tweets.keep_retweets_of.clone(),
+        quotes_only: opts.tweets.quotes_only,
+        exclude_quotes: opts.tweets.exclude_quotes,
+        self_quotes_only: opts.tweets.self_quotes_only,
+        has_photo: opts.tweets.has_photo,
+        has_video: opts.tweets.has_video,
+        has_gif: opts.tweets.has_gif,
+        no_media: opts.tweets.no_media,
+        has_mentions: opts.tweets.has_mentions,
+        no_mentions: opts.tweets.no_mentions,
+        has_hashtags: opts.tweets.has_hashtags,
+        hashtag: opts.tweets.hashtag.clone(),
+        before: opts.tweets.older_than,
+        after: opts.tweets.newer_than,
+        older_than_days: opts.tweets.older_than_days,
+        newer_than_days: opts.tweets.newer_than_days,
+        since_id: opts.tweets.since_id,
+        max_id: opts.tweets.max_id,
+        year: opts.tweets.year.clone(),
+        between_hours: opts.tweets.between_hours,
+        timezone: opts.tweets.timezone,
+        contains: opts.tweets.contains.clone(),
+        exclude_contains: opts.tweets.exclude_contains.clone(),
+        match_regex: opts.tweets.match_regex.clone(),
+        ignore_case: opts.tweets.ignore_case,
+        min_length: opts.tweets.min_length,
+        max_length: opts.tweets.max_length,
+        length_mode: Some(opts.tweets.length_mode),
+        min_favorites: opts.tweets.min_favorites,
+        min_retweets: opts.tweets.min_retweets,
+        delete_popular: opts.tweets.delete_popular,
+        from_client: opts.tweets.from_client.clone(),
+        exclude_client: opts.tweets.exclude_client.clone(),
+        invert: opts.tweets.invert,
+        keep_self_threads: opts.tweets.keep_self_threads,
+        dedupe_threads: opts.tweets.dedupe_threads,
+        delete_pinned: opts.tweets.delete_pinned,
+        keep_first: opts.tweets.keep_first,
+        preserve_media_tweets: opts.tweets.preserve_media_tweets,
+        report_orphans: opts.tweets.report_orphans,
+        keep_orphans: opts.tweets.keep_orphans,
+        orphans_only: opts.tweets.orphans_only,
+    }
+}
+
+/// Loads the cached access token, ignoring it if it was issued for a different consumer key.
+fn load_cached_token(path: &Path, consumer_key: &str) -> Option<egg_mode::KeyPair> {
+    let cached: CachedToken = serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+    if cached.consumer_key != consumer_key {
+        return None;
+    }
+    Some(egg_mode::KeyPair::new(
+        cached.access_token,
+        cached.access_token_secret,
+    ))
+}
+
+/// Writes the access token cache, readable only by the current user on Unix.
+fn save_cached_token(path: &Path, cached: &CachedToken) -> std::io::Result<()> {
+    write_private_file(path, serde_json::to_string(cached)?.as_bytes())
+}
+
+/// Writes a file that only the current user can read on Unix, creating its directory if needed.
+fn write_private_file(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(contents)
+}
+
+/// Returns whether a file in the archive's `data` directory is a part of the data file with the
+/// given base name, e.g. `tweet.js`, `tweets.js` or `tweet-part1.js` for `tweet`.
+fn is_part_file(name: &str, base: &str) -> bool {
+    let name = match name.strip_suffix(".js") {
+        Some(name) => name,
+        None => return false,
+    };
+    let suffix = match name.strip_prefix(base) {
+        Some(suffix) => suffix.strip_prefix('s').unwrap_or(suffix),
+        None => return false,
+    };
+    suffix.is_empty()
+        || suffix
+            .strip_prefix("-part")
+            .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// The longest `window.YTD.<name>.partN =` assignment accepted at the start of an archive file.
+const MAX_ASSIGNMENT_LEN: u64 = 256;
+
+/// Parses the part number out of an assignment of the form `window.YTD.<name>.partN =`. The
+/// name varies between archive versions (`tweet` or `tweets`, for example), so any name is
+/// accepted, and an assignment without a part number is treated as the first part.
+fn parse_archive_part(assignment: &str) -> Option<usize> {
+    let target = assignment.strip_suffix('=')?.trim();
+    let name = target.strip_prefix("window.YTD.")?;
+    match name.rsplit_once(".part") {
+        Some((_, part)) => part.parse().ok(),
+        None => Some(0),
+    }
+}
+
+/// Reads the assignment at the start of an archive file and returns its part number, leaving
+/// the reader at the start of the JSON body.
+fn read_part_number(name: &str, reader: &mut impl BufRead) -> Result<usize, String> {
+    let mut assignment = Vec::new();
+    reader
+        .by_ref()
+        .take(MAX_ASSIGNMENT_LEN)
+        .read_until(b'=', &mut assignment)
+        .map_err(|e| format!("Could not read {}: {}", name, e))?;
+    parse_archive_part(&String::from_utf8_lossy(&assignment)).ok_or_else(|| {
+        unparseable(
+            name,
+            "expected it to start with `window.YTD.<name>.part<N> = `",
+        )
+    })
+}
+
+/// Returns whether the archive path refers to a zipped archive rather than an unzipped
+/// directory. An explicit `.zip` extension always wins.
+fn is_zip_archive(archive_path: &Path) -> bool {
+    archive_path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+        || archive_path.is_file()
+}
+
+/// Calls a function with each element of a JSON array as it's deserialized, so the array is
+/// never held in memory as a whole. The first error the function returns stops the parse, and is
+/// kept in `error`.
+struct ForEach<'f, T, F> {
+    f: &'f mut F,
+    error: &'f mut Option<String>,
+    entry: PhantomData<T>,
+}
+
+impl<'de, T: Deserialize<'de>, F: FnMut(T) -> Result<(), String>> DeserializeSeed<'de>
+    for ForEach<'_, T, F>
+{
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, F: FnMut(T) -> Result<(), String>> Visitor<'de>
+    for ForEach<'_, T, F>
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("an array of archive entries")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(entry) = seq.next_element()? {
+            if let Err(e) = (self.f)(entry) {
+                *self.error = Some(e);
+                return Err(serde::de::Error::custom("stopped"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses the JSON body of an archive file incrementally, calling `f` with each entry.
+fn stream_part<T: DeserializeOwned>(
+    name: &str,
+    reader: impl Read,
+    f: &mut impl FnMut(T) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let mut error = None;
+    let result = ForEach {
+        f,
+        error: &mut error,
+        entry: PhantomData,
+    }
+    .deserialize(&mut deserializer)
+    .and_then(|_| deserializer.end());
+    match (error, result) {
+        (Some(e), _) => Err(e),
+        (None, Err(e)) => Err(unparseable(name, e)),
+        (None, Ok(())) => Ok(()),
+    }
+}
+
+/// Opens an entry of a zipped archive.
+fn open_zip_entry<'a>(
+    zip: &'a mut zip::ZipArchive<std::fs::File>,
+    i: usize,
+    archive_path: &Path,
+) -> Result<zip::read::ZipFile<'a>, String> {
+    zip.by_index(i).map_err(|e| {
+        format!(
+            "Could not read {}, the zip archive may be corrupt: {}",
+            archive_path.display(),
+            e
+        )
+    })
+}
+
+/// Streams the entries of every part of a data file inside a zipped archive, in part order,
+/// without extracting it to disk. Returns `false` if the archive has no such data file.
+fn stream_zipped_parts<T: DeserializeOwned>(
+    archive_path: &Path,
+    base: &str,
+    f: &mut impl FnMut(T) -> Result<(), String>,
+) -> Result<bool, String> {
+    let file = std::fs::File::open(archive_path)
+        .map_err(|e| format!("Could not open {}: {}", archive_path.display(), e))?;
+    let mut zip = zip::ZipArchive::new(file).map_err(|e| {
+        format!(
+            "{} is not a valid zip archive: {}",
+            archive_path.display(),
+            e
+        )
+    })?;
+    // A first pass only reads the assignment at the start of each part, to find the part order.
+    let mut parts = Vec::new();
+    for i in 0..zip.len() {
+        let entry = open_zip_entry(&mut zip, i, archive_path)?;
+        let entry_path = PathBuf::from(entry.name());
+        let in_data_dir = entry_path
+            .parent()
+            .and_then(|parent| parent.file_name())
+            .is_some_and(|parent| parent == "data");
+        let is_part = entry_path
+            .file_name()
+            .is_some_and(|name| is_part_file(&name.to_string_lossy(), base));
+        if in_data_dir && is_part {
+            let name = entry_path.display().to_string();
+            let part = read_part_number(&name, &mut BufReader::new(entry))?;
+            parts.push((part, i, name));
+        }
+    }
+    parts.sort();
+    for (_, i, name) in &parts {
+        let mut reader = BufReader::new(open_zip_entry(&mut zip, *i, archive_path)?);
+        read_part_number(name, &mut reader)?;
+        stream_part(name, reader, f)?;
+    }
+    Ok(!parts.is_empty())
+}
+
+/// Streams the entries of every part of a data file in an unzipped archive's `data` directory,
+/// in part order. Returns `false` if the archive has no such data file.
+fn stream_parts<T: DeserializeOwned>(
+    archive_path: &Path,
+    base: &str,
+    f: &mut impl FnMut(T) -> Result<(), String>,
+) -> Result<bool, String> {
+    let mut parts = std::fs::read_dir(archive_path.join("data"))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| is_part_file(&entry.file_name().to_string_lossy(), base))
+        .map(|entry| {
+            let path = entry.path();
+            let name = path.display().to_string();
+            let mut reader = std::fs::File::open(&path)
+                .map(BufReader::new)
+                .map_err(|e| format!("Could not read {}: {}", name, e))?;
+            Ok((read_part_number(&name, &mut reader)?, name, reader))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    parts.sort_by_key(|(part, _, _)| *part);
+    let found = !parts.is_empty();
+    for (_, name, reader) in parts {
+        stream_part(&name, reader, f)?;
+    }
+    Ok(found)
+}
+
+/// Returns a message explaining that a data file in the archive couldn't be parsed.
+fn unparseable(name: &str, reason: impl std::fmt::Display) -> String {
+    format!(
+        "Could not parse {}: {}. The Twitter archive format may have changed; please report this along with the first few lines of the file.",
+        name, reason
+    )
+}
+
+/// Deserializes an entry from the archive, with a clear message if it has an unexpected shape.
+fn from_archive_value<T: DeserializeOwned>(name: &str, raw: Value) -> Result<T, String> {
+    serde_json::from_value(raw).map_err(|e| unparseable(name, e))
+}
+
+/// Calls `f` with each entry from every part of a data file in the archive, in part order, until
+/// it returns an error. The parts are parsed incrementally, so only the entries `f` keeps stay in
+/// memory. Returns `false` if the archive doesn't contain the data file at all.
+fn for_each_entry<T: DeserializeOwned>(
+    archive_path: &Path,
+    base: &str,
+    mut f: impl FnMut(T) -> Result<(), String>,
+) -> Result<bool, String> {
+    if is_zip_archive(archive_path) {
+        stream_zipped_parts(archive_path, base, &mut f)
+    } else {
+        stream_parts(archive_path, base, &mut f)
+    }
+}
+
+/// Like `for_each_entry`, for a data file the archive must contain, failing if it's missing.
+fn for_each_required_entry<T: DeserializeOwned>(
+    archive_path: &Path,
+    base: &str,
+    f: impl FnMut(T) -> Result<(), String>,
+) -> Result<(), String> {
+    if for_each_entry(archive_path, base, f)? {
+        return Ok(());
+    }
+    Err(missing_data_file(archive_path, base))
+}
+
+/// Returns a message explaining that the archive doesn't contain a data file it must have.
+fn missing_data_file(archive_path: &Path, base: &str) -> String {
+    let names = if base.ends_with('s') {
+        format!("data/{}.js", base)
+    } else {
+        format!("data/{}.js or data/{}s.js", base, base)
+    };
+    format!(
+        "Could not find {} in {}; is this a Twitter archive (either the downloaded .zip file or the directory it was unzipped into)?",
+        names,
+        archive_path.display()
+    )
+}
+
+/// Returns the loaded data, or exits with the error if the archive couldn't be read.
+fn or_exit<T>(result: Result<T, String>) -> T {
+    result.unwrap_or_else(|e| {
+        error!("🚨 {}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Loads the details of the account the archive was exported from, if the archive has them.
+fn load_account(archive_path: &Path) -> Result<Option<Account>, String> {
+    let mut account = None;
+    for_each_entry(archive_path, "account", |a: WrappedAccount| {
+        account.get_or_insert(a.account);
+        Ok(())
+    })?;
+    Ok(account)
+}
+
+/// Calls `f` with each tweet in the archive, until it returns an error. Archives without
+/// `data/tweet.js` fall back to `data/tweet-headers.js`, which only has the IDs and dates of the
+/// tweets.
+fn for_each_tweet(
+    archive_path: &Path,
+    mut f: impl FnMut(WrappedTweet) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut read = |name: &str, raw: Value| {
+        let tweet = Tweet::from_value(&raw).map_err(|e| unparseable(name, e))?;
+        f(WrappedTweet { tweet, raw })
+    };
+    if for_each_entry(archive_path, "tweet", |raw| read("data/tweet.js", raw))? {
+        return Ok(());
+    }
+    let mut warned = false;
+    let found = for_each_entry(archive_path, "tweet-headers", |raw| {
+        if !warned {
+            warn!("⚠️ The archive only has tweet headers, so the tweets have no text, media or replies to filter on");
+            warned = true;
+        }
+        read("data/tweet-headers.js", raw)
+    })?;
+    if found {
+        Ok(())
+    } else {
+        Err(missing_data_file(archive_path, "tweet"))
+    }
+}
+
+/// Loads and de-duplicates the tweets from the archive that pass the `keep` filter.
+fn load_tweets(
+    archive_path: &Path,
+    mut keep: impl FnMut(&WrappedTweet) -> bool,
+) -> Result<Vec<WrappedTweet>, String> {
+    let mut seen = HashSet::new();
+    let mut tweets = Vec::new();
+    for_each_tweet(archive_path, |tweet| {
+        if let Some(Err(e)) = &tweet.tweet.created_at {
+            let fallback = if tweet.tweet.created_at().is_some() {
+                "using the date in its ID instead"
+            } else {
+                "treating it as undated"
+            };
+            warn!(
+                "⚠️ Could not read the date of tweet {}: {}, {}",
+                tweet.tweet.id, e, fallback
+            );
+        }
+        if keep(&tweet) && seen.insert(tweet.tweet.id.clone()) {
+            tweets.push(tweet);
+        }
+        Ok(())
+    })?;
+    Ok(tweets)
+}
+
+/// The most tweets twitter returns from an account's timeline, however far back it's paged.
+const TIMELINE_CAP: usize = 3200;
+
+/// Reads the tweets on an account's timeline from twitter, newest first, paging back with
+/// `max_id` until twitter has no older ones. Twitter only goes back [`TIMELINE_CAP`] tweets,
+/// retweets included. The rate limit is waited out, and other transient failures are retried
+/// like deletions are.
+async fn fetch_timeline(
+    user_id: u64,
+    token: &egg_mode::Token,
+    opts: &Opts,
+    spinner: &ProgressBar,
+) -> Result<Vec<WrappedTweet>, String> {
+    let mut tweets = Vec::new();
+    let mut max_id: Option<u64> = None;
+    let mut attempt = 0;
+    loop {
+        let mut params = egg_mode::raw::ParamList::new()
+            .add_param("user_id", user_id.to_string())
+            .add_param("count", "200")
+            .add_param("tweet_mode", "extended")
+            .add_param("include_rts", "true");
+        if let Some(max_id) = max_id {
+            params = params.add_param("max_id", max_id.to_string());
+        }
+        let request = egg_mode::raw::request_get(
+            "https://api.twitter.com/1.1/statuses/user_timeline.json",
+            token,
+            Some(&params),
+        );
+        let page = match net::send_json::<Vec<Value>>(request).await {
+            Ok(response) => response.response,
+            Err(e) => {
+                match classify_error(&e) {
+                    Failure::RateLimited(reset) => {
+                        let now = Utc::now().timestamp();
+                        let reset = reset.map_or(now + RATE_LIMIT_WINDOW_SECS, i64::from);
+                        spinner.set_message(format!(
+                            "⏳ Rate limited, reading more of your timeline at {}",
+                            Utc.timestamp(reset, 0)
+                                .with_timezone(&Local)
+                                .format("%H:%M:%S")
+                        ));
+                        tokio::time::sleep(Duration::from_secs((reset - now).max(0) as u64)).await;
+                    }
+                    Failure::Retryable if attempt < opts.common.max_retries => {
+                        tokio::time::sleep(backoff_delay(attempt)).await;
+                        attempt += 1;
+                    }
+                    _ => return Err(format!("Could not read your timeline: {}", e)),
+                }
+                continue;
+            }
+        };
+        attempt = 0;
+        let before = tweets.len();
+        for raw in page {
+            let tweet = Tweet::from_value(&raw)
+                .map_err(|e| format!("Could not read a tweet on your timeline: {}", e))?;
+            if let Ok(id) = tweet.id.parse::<u64>() {
+                max_id = Some(max_id.map_or(id, |max_id| max_id.min(id)));
+            }
+            tweets.push(WrappedTweet { tweet, raw });
+        }
+        // The next page starts below the oldest tweet on this one.
+        match max_id {
+            Some(id) if tweets.len() > before && id > 0 => max_id = Some(id - 1),
+            _ => break,
+        }
+        spinner.set_message(format!(
+            "Reading your timeline, {} tweets so far",
+            tweets.len()
+        ));
+    }
+    if tweets.len() >= TIMELINE_CAP {
+        warn!(
+            "⚠️ Twitter only shows your latest {} tweets on your timeline, use `damae tweets` with your archive to reach older ones",
+            TIMELINE_CAP
+        );
+    }
+    Ok(tweets)
+}
+
+/// The reply links between tweets in the archive, used to find self-threads.
+#[derive(Default)]
+struct ThreadIndex {
+    ids: HashSet<String>,
+    /// Pairs of a reply and the tweet it replies to.
+    replies: Vec<(String, String)>,
+}
+
+impl ThreadIndex {
+    fn record(&mut self, tweet: &Tweet) {
+        self.ids.insert(tweet.id.clone());
+        if let Some(parent) = &tweet.in_reply_to_status_id {
+            self.replies.push((tweet.id.clone(), parent.clone()));
+        }
+    }
+
+    /// Returns every tweet that's part of a self-thread. Every tweet in the archive was posted
+    /// by the account, so a reply to a tweet that's also in the archive is a self-reply, and
+    /// every tweet on either end of one belongs to a thread.
+    fn self_thread_ids(&self) -> HashSet<&str> {
+        self.replies
+            .iter()
+            .filter(|(_, parent)| self.ids.contains(parent))
+            .flat_map(|(reply, parent)| [reply.as_str(), parent.as_str()])
+            .collect()
+    }
+
+    /// Returns the tweets that end a self-thread: self-replies that no tweet in the archive
+    /// replies to. A thread that branches, with two replies to the same tweet, has a last tweet
+    /// at the end of each branch. Tweets that aren't part of any self-thread are never included.
+    fn self_thread_leaves(&self) -> HashSet<&str> {
+        let replied_to = self
+            .replies
+            .iter()
+            .map(|(_, parent)| parent.as_str())
+            .collect::<HashSet<_>>();
+        self.replies
+            .iter()
+            .filter(|(reply, parent)| {
+                self.ids.contains(parent) && !replied_to.contains(reply.as_str())
+            })
+            .map(|(reply, _)| reply.as_str())
+            .collect()
+    }
+
+    /// Returns the orphans: replies to a tweet that isn't in the archive, either someone else's
+    /// or one of the account's own that was deleted before the archive was made.
+    fn orphan_ids(&self) -> HashSet<&str> {
+        self.replies
+            .iter()
+            .filter(|(_, parent)| !self.ids.contains(parent))
+            .map(|(reply, _)| reply.as_str())
+            .collect()
+    }
+
+    /// Returns the first tweet of the self-thread each self-reply belongs to.
+    fn self_thread_roots(&self) -> HashMap<&str, &str> {
+        let parents = self
+            .replies
+            .iter()
+            .filter(|(_, parent)| self.ids.contains(parent))
+            .map(|(reply, parent)| (reply.as_str(), parent.as_str()))
+            .collect::<HashMap<_, _>>();
+        parents
+            .keys()
+            .map(|&reply| {
+                let mut root = reply;
+                // Bounded, in case a broken archive has a reply loop.
+                for _ in 0..parents.len() {
+                    match parents.get(root) {
+                        Some(parent) => root = parent,
+                        None => break,
+                    }
+                }
+                (reply, root)
+            })
+            .collect()
+    }
+}
+
+/// Loads and de-duplicates the likes from the archive.
+fn load_likes(archive_path: &Path) -> Result<Vec<WrappedLike>, String> {
+    let mut seen = HashSet::new();
+    let mut likes = Vec::new();
+    for_each_required_entry(archive_path, "like", |raw: Value| {
+        let like = WrappedLike {
+            raw: raw.clone(),
+            ..from_archive_value("data/like.js", raw)?
+        };
+        if seen.insert(like.like.tweet_id.clone()) {
+            likes.push(like);
+        }
+        Ok(())
+    })?;
+    Ok(likes)
+}
+
+/// Loads and de-duplicates the blocked or muted accounts from the archive, where each entry holds
+/// the account under `key` (`blocking` or `muting`).
+fn load_related_accounts(
+    archive_path: &Path,
+    base: &str,
+    key: &str,
+) -> Result<Vec<RelatedAccount>, String> {
+    let name = format!("data/{}.js", base);
+    let mut seen = HashSet::new();
+    let mut accounts = Vec::new();
+    for_each_required_entry(archive_path, base, |mut raw: Value| {
+        let account = RelatedAccount {
+            raw: raw.clone(),
+            ..from_archive_value(&name, raw[key].take())?
+        };
+        if seen.insert(account.account_id.clone()) {
+            accounts.push(account);
+        }
+        Ok(())
+    })?;
+    Ok(accounts)
+}
+
+/// Loads the direct messages from every conversation in the archive.
+fn load_direct_messages(archive_path: &Path) -> Result<Vec<DirectMessage>, String> {
+    let mut messages = Vec::new();
+    for_each_required_entry(archive_path, "direct-messages", |c: WrappedConversation| {
+        for raw in c
+            .dm_conversation
+            .messages
+            .into_iter()
+            .filter_map(|event| event.message_create)
+        {
+            messages.push(DirectMessage {
+                raw: raw.clone(),
+                ..from_archive_value("data/direct-messages.js", raw)?
+            });
+        }
+        Ok(())
+    })?;
+    Ok(messages)
+}
+
+/// Prints the clients the archive's tweets were posted from, most used first.
+fn list_clients(archive_path: &Path) {
+    let mut seen = HashSet::new();
+    let mut counts = HashMap::<String, usize>::new();
+    let result = for_each_tweet(archive_path, |t| {
+        if seen.insert(t.tweet.id.clone()) {
+            let client = t.tweet.client().unwrap_or("(unknown)").to_string();
+            *counts.entry(client).or_insert(0) += 1;
+        }
+        Ok(())
+    });
+    or_exit(result);
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    counts.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then_with(|| a_name.cmp(b_name)));
+    for (client, count) in counts {
+        println!("{:>7} {}", count, client);
+    }
+}
+
+/// Returns whether the text contains the given string, optionally ignoring case. Both are NFC
+/// normalized first, so an accented letter matches whether it was typed as one character or as a
+/// letter followed by a combining accent. Case is ignored with Unicode's lowercase mapping, which
+/// doesn't depend on the language: `I` lowercases to `i`, not to the Turkish dotless `ı`.
+fn text_contains(text: &str, needle: &str, ignore_case: bool) -> bool {
+    let normalize = |s: &str| -> String {
+        if ignore_case {
+            // Lowercasing can leave combining marks behind (`İ` becomes `i` and a combining dot),
+            // so the result is normalized again.
+            s.nfc().collect::<String>().to_lowercase().nfc().collect()
+        } else {
+            s.nfc().collect()
+        }
+    };
+    normalize(text).contains(&normalize(needle))
+}
+
+/// Returns whether a tweet passes every filter in [`filters::FILTERS`], as selected on the
+/// command line. `username` and `account_id` are the archive's account, which tweets are
+/// compared against to tell which ones quote or reply to your own.
+fn keep_tweet(
+    t: &WrappedTweet,
+    opts: &Opts,
+    dates: DateRange,
+    match_regex: Option<&Regex>,
+    username: Option<&str>,
+    account_id: Option<&str>,
+) -> bool {
+    let context = filters::Context {
+        opts: &opts.tweets,
+        dates,
+        match_regex,
+        username,
+        account_id,
+    };
+    filters::FILTERS
+        .iter()
+        .all(|filter| (filter.keeps)(&t.tweet, &context))
+}
+
+/// Spaces out deletion requests evenly to stay under the --rate-limit, shared by every task.
+struct Throttle {
+    interval: Option<Mutex<tokio::time::Interval>>,
+}
+
+impl Throttle {
+    /// Creates a throttle allowing `per_minute` requests per minute, or any number if it's 0.
+    fn new(per_minute: u32) -> Self {
+        let interval = (per_minute > 0).then(|| {
+            let mut interval = tokio::time::interval(Duration::from_secs(60) / per_minute);
+            // Requests that were held up by something else shouldn't be made up for in a burst.
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            Mutex::new(interval)
+        });
+        Throttle { interval }
+    }
+
+    /// Waits until the next request is allowed.
+    async fn wait(&self) {
+        if let Some(interval) = &self.interval {
+            interval.lock().await.tick().await;
+        }
+    }
+}
+
+/// How many requests --concurrency-auto allows in flight at the start of a run.
+const AUTO_CONCURRENCY_START: usize = 2;
+
+/// Decides how many requests may be in flight with --concurrency-auto, using additive increase
+/// and multiplicative decrease: every time as many requests in a row succeed as are allowed in
+/// flight, with the rate limit window not close to running out, one more is allowed, up to
+/// --max-tasks. Whenever a request hits the rate limit or fails in a way that's worth retrying,
+/// the limit is halved. A burst of failures only halves it once per request in flight, so
+/// requests that were already sent when the first one failed don't shrink it further.
+struct Controller {
+    limit: usize,
+    max: usize,
+    /// Successes since the limit last changed.
+    successes: usize,
+    /// Failures to ignore, because their requests were sent before the limit was last lowered.
+    grace: usize,
+}
+
+impl Controller {
+    fn new(max: usize) -> Self {
+        Controller {
+            limit: AUTO_CONCURRENCY_START.min(max).max(1),
+            max: max.max(1),
+            successes: 0,
+            grace: 0,
+        }
+    }
+
+    /// Records a successful request, given the rate limit status twitter returned with it.
+    fn success(&mut self, status: &egg_mode::RateLimit) {
+        self.grace = self.grace.saturating_sub(1);
+        // Only ramp up if the window could cover twice the requests in flight after the increase.
+        let headroom = status.remaining < 0 || status.remaining as usize > 2 * (self.limit + 1);
+        if !headroom {
+            self.successes = 0;
+            return;
+        }
+        self.successes += 1;
+        if self.successes >= self.limit && self.limit < self.max {
+            self.limit += 1;
+            self.successes = 0;
+        }
+    }
+
+    /// Records a request that hit the rate limit or failed in a way that's worth retrying.
+    fn failure(&mut self) {
+        self.successes = 0;
+        if self.grace > 0 {
+            self.grace -= 1;
+            return;
+        }
+        self.grace = self.limit - 1;
+        self.limit = (self.limit / 2).max(1);
+    }
+}
+
+/// Limits how many requests are in flight at once, to a number that --concurrency-auto adjusts as
+/// the run goes on. Without it, --max-tasks alone limits the requests.
+struct Concurrency {
+    controller: Option<std::sync::Mutex<(Controller, usize)>>,
+    released: tokio::sync::Notify,
+}
+
+/// Allows one request to be in flight, until it's dropped.
+struct Permit<'a> {
+    concurrency: &'a Concurrency,
+}
+
+impl Concurrency {
+    fn new(opts: &Opts) -> Self {
+        Concurrency {
+            controller: opts
+                .common
+                .concurrency_auto
+                .then(|| std::sync::Mutex::new((Controller::new(opts.common.max_tasks), 0))),
+            released: tokio::sync::Notify::new(),
+        }
+    }
+
+    /// Waits until another request is allowed to be in flight.
+    async fn acquire(&self) -> Permit<'_> {
+        if let Some(controller) = &self.controller {
+            loop {
+                {
+                    let mut guard = controller.lock().unwrap();
+                    let (controller, in_flight) = &mut *guard;
+                    if *in_flight < controller.limit {
+                        *in_flight += 1;
+                        break;
+                    }
+                }
+                // Each release wakes one waiting request, or the next one to wait if there are
+                // none yet, so none are missed between the check and here.
+                self.released.notified().await;
+            }
+        }
+        Permit { concurrency: self }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Controller)) {
+        if let Some(controller) = &self.controller {
+            let mut guard = controller.lock().unwrap();
+            let before = guard.0.limit;
+            f(&mut guard.0);
+            if guard.0.limit != before {
+                debug!("Allowing {} requests in flight", guard.0.limit);
+            }
+            if guard.0.limit > before {
+                self.released.notify_one();
+            }
+        }
+    }
+
+    fn success(&self, status: &egg_mode::RateLimit) {
+        self.update(|controller| controller.success(status));
+    }
+
+    fn failure(&self) {
+        self.update(Controller::failure);
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if let Some(controller) = &self.concurrency.controller {
+            controller.lock().unwrap().1 -= 1;
+            self.concurrency.released.notify_one();
+        }
+    }
+}
+
+/// Returns whether the remaining calls in the rate limit window are too few to cover every
+/// request that may already be in flight.
+fn rate_limit_exhausted(status: &egg_mode::RateLimit, max_tasks: usize) -> bool {
+    status.remaining >= 0 && status.reset > 0 && status.remaining as usize <= max_tasks
+}
+
+/// Sleeps until the rate limit window resets, if the last response reported it as exhausted.
+async fn wait_for_rate_limit(
+    rate_limit_reset: &Mutex<Option<i32>>,
+    rate_limit_waits: &AtomicUsize,
+    pb: &ProgressBar,
+) {
+    let reset = match *rate_limit_reset.lock().await {
+        Some(reset) => reset,
+        None => return,
+    };
+    let wait = i64::from(reset) - Utc::now().timestamp();
+    if wait > 0 {
+        let resume_at = Utc
+            .timestamp(i64::from(reset), 0)
+            .with_timezone(&chrono::Local);
+        pb.set_message(format!(
+            "⏳ Rate limited, resuming at {}",
+            resume_at.format("%H:%M:%S")
+        ));
+        rate_limit_waits.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_secs(wait as u64)).await;
+        pb.set_message("");
+    }
+}
+
+/// How long to wait after hitting the rate limit when twitter doesn't say when it resets, the
+/// length of twitter's rate limit windows.
+const RATE_LIMIT_WINDOW_SECS: i64 = 15 * 60;
+
+/// How a failed request should be handled.
+#[derive(Debug, PartialEq)]
+enum Failure {
+    /// The item was already removed, or never existed (error codes 144 and 34), so there is
+    /// nothing left to do.
+    AlreadyDeleted,
+    /// Twitter's rate limit ran out (error code 88). The request should be tried again once the
+    /// limit resets, at the given Unix timestamp if twitter said when.
+    RateLimited(Option<i32>),
+    /// The request may succeed if it's tried again later.
+    Retryable,
+    /// Trying again won't help.
+    Fatal,
+    /// The account can't delete anything at all, because it's suspended or locked or the token
+    /// is no longer valid (error codes 64, 326, and 89). Every other request would fail the same
+    /// way, so the run should stop.
+    Account(i32),
+}
+
+/// Returns what to tell the user when twitter rejects every request because of the account
+/// itself, with the error code from [`Failure::Account`].
+fn describe_account_error(code: i32) -> &'static str {
+    match code {
+        64 => "Your account is suspended, so twitter won't let it delete anything",
+        326 => {
+            "Your account is locked, log in on twitter.com to unlock it and then run Damae again"
+        }
+        _ => "The access token is invalid or has expired, pass --logout to log in again",
+    }
+}
+
+/// What happened to an item that the tool attempted to remove.
+enum Deletion {
+    Deleted,
+    AlreadyDeleted,
+}
+
+/// Decides whether a failed request should be retried.
+fn classify_error(e: &egg_mode::error::Error) -> Failure {
+    use egg_mode::error::Error;
+    match e {
+        Error::TwitterError(headers, te) => {
+            if let Some(ec) = te.errors.iter().find(|ec| matches!(ec.code, 64 | 89 | 326)) {
+                Failure::Account(ec.code)
+            } else if te.errors.iter().any(|ec| matches!(ec.code, 34 | 144)) {
+                Failure::AlreadyDeleted
+            } else if te.errors.iter().any(|ec| ec.code == 88) {
+                Failure::RateLimited(
+                    headers
+                        .get("x-rate-limit-reset")
+                        .and_then(|reset| reset.to_str().ok())
+                        .and_then(|reset| reset.parse().ok()),
+                )
+            } else if !te.errors.is_empty()
+                && te.errors.iter().all(|ec| matches!(ec.code, 130 | 131))
+            {
+                // Over capacity, or an internal error
+                Failure::Retryable
+            } else {
+                Failure::Fatal
+            }
+        }
+        Error::BadStatus(status) => {
+            if status.as_u16() == 429 {
+                Failure::RateLimited(None)
+            } else if status.is_server_error() {
+                Failure::Retryable
+            } else {
+                Failure::Fatal
+            }
+        }
+        Error::RateLimit(reset) => Failure::RateLimited(Some(*reset)),
+        Error::NetError(_) | Error::IOError(_) | Error::TlsError(_) => Failure::Retryable,
+        _ => Failure::Fatal,
+    }
+}
+
+/// Returns the twitter error codes behind a failed request, if twitter reported any.
+fn twitter_error_codes(e: &egg_mode::error::Error) -> Vec<i32> {
+    match e {
+        egg_mode::error::Error::TwitterError(_, te) => te.errors.iter().map(|ec| ec.code).collect(),
+        egg_mode::error::Error::RateLimit(_) => vec![88],
+        _ => Vec::new(),
+    }
+}
+
+/// Returns a short description of a twitter error code, for the failure breakdown.
+fn describe_error_code(code: i32) -> &'static str {
+    match code {
+        32 => "could not authenticate",
+        34 => "not found",
+        63 => "user suspended",
+        64 => "account suspended",
+        88 => "rate limit",
+        89 => "invalid token",
+        130 => "over capacity",
+        131 => "internal error",
+        144 => "already deleted",
+        179 => "not authorized",
+        326 => "account locked",
+        _ => "error",
+    }
+}
+
+/// Formats the failure counts for each error code as a single line, e.g.
+/// `34 not found: 12, 88 rate limit: 5`.
+fn format_error_codes(error_codes: &BTreeMap<i32, usize>) -> String {
+    error_codes
+        .iter()
+        .map(|(code, count)| format!("{} {}: {}", code, describe_error_code(*code), count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns how long to wait before the given retry attempt, doubling each time with up to a
+/// second of random jitter.
+fn backoff_delay(attempt: u32) -> Duration {
+    Duration::from_secs(2u64.saturating_pow(attempt))
+        + Duration::from_millis(rand::thread_rng().gen_range(0..1000))
+}
+
+/// Looks up the ID of the tweet pinned to an account's profile. The v1.1 user object egg_mode
+/// parses doesn't include it, so this asks the v2 users endpoint instead.
+async fn pinned_tweet_id(
+    user_id: u64,
+    token: &egg_mode::Token,
+) -> Result<Option<String>, egg_mode::error::Error> {
+    #[derive(Deserialize, Default)]
+    struct UserResponse {
+        data: UserData,
+    }
+    #[derive(Deserialize, Default)]
+    struct UserData {
+        pinned_tweet_id: Option<String>,
+    }
+    let params = egg_mode::raw::ParamList::new().add_param("user.fields", "pinned_tweet_id");
+    let request = egg_mode::raw::request_get(
+        &format!("https://api.twitter.com/2/users/{}", user_id),
+        token,
+        Some(&params),
+    );
+    let response: egg_mode::Response<UserResponse> = net::send_json(request).await?;
+    Ok(response.response.data.pinned_tweet_id)
+}
+
+/// Performs the action for the selected mode on a single item, returning the rate limit status
+/// reported by twitter. OAuth 2.0 tokens only work with the v2 API, so those go through the v2
+/// endpoints instead.
+async fn perform(
+    mode: Mode,
+    target: &Target,
+    token: &egg_mode::Token,
+    user_id: u64,
+) -> Result<egg_mode::RateLimit, egg_mode::error::Error> {
+    let id = target
+        .id
+        .parse::<u64>()
+        .expect("IDs are checked when the archive is loaded");
+    if let egg_mode::Token::Bearer(_) = token {
+        return match (mode, &target.retweeted_status_id) {
+            (Mode::Tweets | Mode::Timeline, Some(original_id)) => {
+                let original_id = original_id
+                    .parse::<u64>()
+                    .expect("IDs are checked when the archive is loaded");
+                oauth2::unretweet(user_id, original_id, token).await
+            }
+            (Mode::Tweets | Mode::Timeline, None) => oauth2::delete_tweet(id, token).await,
+            (Mode::Likes, _) => oauth2::unlike(user_id, id, token).await,
+            (Mode::Dms, _) => unreachable!("direct messages can't be deleted with --oauth2"),
+            (Mode::Unblock, _) => oauth2::unblock(user_id, id, token).await,
+            (Mode::Unmute, _) => oauth2::unmute(user_id, id, token).await,
+        };
+    }
+    let request = match (mode, &target.retweeted_status_id) {
+        (Mode::Tweets | Mode::Timeline, Some(original_id)) => egg_mode::raw::request_post(
+            &format!(
+                "https://api.twitter.com/1.1/statuses/unretweet/{}.json",
+                original_id
+            ),
+            token,
+            None,
+        ),
+        (Mode::Tweets | Mode::Timeline, None) => egg_mode::raw::request_post(
+            &format!("https://api.twitter.com/1.1/statuses/destroy/{}.json", id),
+            token,
+            None,
+        ),
+        (Mode::Likes, _) => egg_mode::raw::request_post(
+            "https://api.twitter.com/1.1/favorites/destroy.json",
+            token,
+            Some(&egg_mode::raw::ParamList::new().add_param("id", id.to_string())),
+        ),
+        (Mode::Dms, _) => egg_mode::raw::request_delete(
+            "https://api.twitter.com/1.1/direct_messages/events/destroy.json",
+            token,
+            Some(&egg_mode::raw::ParamList::new().add_param("id", id.to_string())),
+        ),
+        (Mode::Unblock, _) => egg_mode::raw::request_post(
+            "https://api.twitter.com/1.1/blocks/destroy.json",
+            token,
+            Some(&egg_mode::raw::ParamList::new().add_param("user_id", id.to_string())),
+        ),
+        (Mode::Unmute, _) => egg_mode::raw::request_post(
+            "https://api.twitter.com/1.1/mutes/users/destroy.json",
+            token,
+            Some(&egg_mode::raw::ParamList::new().add_param("user_id", id.to_string())),
+        ),
+    };
+    net::send_empty(request).await
+}
+
+/// Fails a request with an IO error if it doesn't finish within the timeout, so it's retried like
+/// any other network failure.
+async fn with_timeout<T>(
+    timeout: Option<Duration>,
+    request: impl std::future::Future<Output = Result<T, egg_mode::error::Error>>,
+) -> Result<T, egg_mode::error::Error> {
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return request.await,
+    };
+    tokio::time::timeout(timeout, request)
+        .await
+        .unwrap_or_else(|_| {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("request timed out after {}", HumanDuration(timeout)),
+            )
+            .into())
+        })
+}
+
+#[derive(Deserialize)]
+struct PostedTweet {
+    id_str: String,
+}
+
+/// Posts a tweet, as a reply if `in_reply_to` is set, and returns its ID. v1.1 and v2 both fill
+/// in the @mentions of a reply themselves.
+async fn post_tweet(
+    text: &str,
+    in_reply_to: Option<&str>,
+    token: &egg_mode::Token,
+) -> Result<String, egg_mode::error::Error> {
+    if let egg_mode::Token::Bearer(_) = token {
+        return oauth2::post_tweet(text, in_reply_to, token).await;
+    }
+    let mut params = egg_mode::raw::ParamList::new().add_param("status", text.to_string());
+    if let Some(id) = in_reply_to {
+        params = params
+            .add_param("in_reply_to_status_id", id.to_string())
+            .add_param("auto_populate_reply_metadata", "true");
+    }
+    let request = egg_mode::raw::request_post(
+        "https://api.twitter.com/1.1/statuses/update.json",
+        token,
+        Some(&params),
+    );
+    let response: egg_mode::Response<PostedTweet> = net::send_json(request).await?;
+    Ok(response.response.id_str)
+}
+
+/// Posts the redacted copy of a tweet and returns its ID. Only the rate limit is waited out:
+/// after any other failure twitter may have posted the copy anyway, and trying again could post
+/// it twice.
+async fn post_repost(
+    repost: &Repost,
+    token: &egg_mode::Token,
+    opts: &Opts,
+    rate_limit_reset: &Mutex<Option<i32>>,
+    rate_limit_waits: &AtomicUsize,
+    pb: &ProgressBar,
+) -> Result<String, egg_mode::error::Error> {
+    loop {
+        if !opts.common.ignore_rate_limit {
+            wait_for_rate_limit(rate_limit_reset, rate_limit_waits, pb).await;
+        }
+        let request = post_tweet(&repost.text, repost.in_reply_to_status_id.as_deref(), token);
+        match with_timeout(
+            opts.common.request_timeout.map(Duration::from_secs),
+            request,
+        )
+        .await
+        {
+            Ok(id) => return Ok(id),
+            Err(e) => match classify_error(&e) {
+                Failure::RateLimited(reset) => {
+                    let reset = reset.unwrap_or_else(|| {
+                        (Utc::now().timestamp() + RATE_LIMIT_WINDOW_SECS) as i32
+                    });
+                    *rate_limit_reset.lock().await = Some(reset);
+                    wait_for_rate_limit(rate_limit_reset, rate_limit_waits, pb).await;
+                }
+                _ => return Err(e),
+            },
+        }
+    }
+}
+
+/// Performs the action for the selected mode on an item, retrying transient failures with
+/// exponential backoff. Items that were already removed count as a success.
+#[allow(clippy::too_many_arguments)]
+async fn remove(
+    target: &Target,
+    token: &egg_mode::Token,
+    user_id: u64,
+    opts: &Opts,
+    rate_limit_reset: &Mutex<Option<i32>>,
+    rate_limit_waits: &AtomicUsize,
+    throttle: &Throttle,
+    concurrency: &Concurrency,
+    pb: &ProgressBar,
+) -> Result<Deletion, egg_mode::error::Error> {
+    let mut attempt = 0;
+    loop {
+        if !opts.common.ignore_rate_limit {
+            wait_for_rate_limit(rate_limit_reset, rate_limit_waits, pb).await;
+        }
+        let permit = concurrency.acquire().await;
+        throttle.wait().await;
+        let request = perform(opts.mode, target, token, user_id);
+        let result = with_timeout(
+            opts.common.request_timeout.map(Duration::from_secs),
+            request,
+        )
+        .await;
+        drop(permit);
+        match result {
+            Ok(rate_limit_status) => {
+                concurrency.success(&rate_limit_status);
+                if rate_limit_exhausted(&rate_limit_status, opts.common.max_tasks) {
+                    *rate_limit_reset.lock().await = Some(rate_limit_status.reset);
+                }
+                return Ok(Deletion::Deleted);
+            }
+            Err(e) => {
+                match classify_error(&e) {
+                    Failure::AlreadyDeleted if !opts.common.no_skip_missing => {
+                        return Ok(Deletion::AlreadyDeleted)
+                    }
+                    // Hitting the rate limit says nothing about the item itself, so it's tried
+                    // again once the limit resets without using up any of its retries.
+                    Failure::RateLimited(reset) => {
+                        concurrency.failure();
+                        let reset = reset.unwrap_or_else(|| {
+                            (Utc::now().timestamp() + RATE_LIMIT_WINDOW_SECS) as i32
+                        });
+                        *rate_limit_reset.lock().await = Some(reset);
+                        wait_for_rate_limit(rate_limit_reset, rate_limit_waits, pb).await;
+                    }
+                    Failure::Retryable if attempt < opts.common.max_retries => {
+                        concurrency.failure();
+                        tokio::time::sleep(backoff_delay(attempt)).await;
+                        attempt += 1;
+                    }
+                    _ => return Err(e),
+                }
+            }
+        }
+    }
+}
+
+/// Reads a list of tweet IDs from a file, either one per line or a JSON array in the twitter
+/// archive format (like `data/like.js`), depending on what the file looks like.
+fn read_id_list(path: &Path) -> Result<HashSet<String>, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let trimmed = contents.trim_start();
+    if trimmed.starts_with("window.YTD.") || trimmed.starts_with('[') {
+        parse_json_id_list(trimmed)
+    } else {
+        parse_id_list(&contents).map_err(|e| {
+            format!(
+                "{} (expected one tweet ID per line, or a JSON array from a twitter archive)",
+                e
+            )
+        })
+    }
+}
+
+/// Parses the tweet IDs out of a JSON array of archive entries, with or without the
+/// `window.YTD.<name>.part<N> =` prefix of the archive's data files. Entries can be likes,
+/// tweets, bare objects with a tweet ID, or the IDs themselves.
+fn parse_json_id_list(contents: &str) -> Result<HashSet<String>, String> {
+    let json = match contents.strip_prefix("window.YTD.") {
+        Some(rest) => {
+            rest.split_once('=')
+                .ok_or("missing `=` after the window.YTD prefix")?
+                .1
+        }
+        None => contents,
+    };
+    let entries: Vec<Value> =
+        serde_json::from_str(json).map_err(|e| format!("not a valid JSON array: {}", e))?;
+    let mut ids = HashSet::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let inner = entry
+            .get("like")
+            .or_else(|| entry.get("tweet"))
+            .unwrap_or(entry);
+        let id = match inner {
+            Value::String(_) | Value::Number(_) => Some(inner),
+            _ => ["tweetId", "id_str", "id"]
+                .iter()
+                .find_map(|key| inner.get(key)),
+        };
+        let id = match id {
+            Some(Value::String(id)) => id.clone(),
+            Some(Value::Number(id)) => id.to_string(),
+            _ => return Err(format!("entry {} doesn't have a tweet ID", i + 1)),
+        };
+        if id.parse::<u64>().is_err() {
+            return Err(format!(
+                "entry {}: \"{}\" is not a valid tweet ID",
+                i + 1,
+                id
+            ));
+        }
+        ids.insert(id);
+    }
+    Ok(ids)
+}
+
+/// Parses a newline-delimited list of tweet IDs, skipping blank lines and `#` comments.
+fn parse_id_list(contents: &str) -> Result<HashSet<String>, String> {
+    let mut ids = HashSet::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.parse::<u64>().is_err() {
+            return Err(format!(
+                "line {}: \"{}\" is not a valid tweet ID",
+                i + 1,
+                line
+            ));
+        }
+        ids.insert(line.to_string());
+    }
+    Ok(ids)
+}
+
+/// Writes the archive entries for every target to a JSON file, in the same shape as the archive.
+fn write_backup(path: &Path, targets: &[Target]) -> std::io::Result<()> {
+    let entries = targets.iter().map(|t| &t.raw).collect::<Vec<_>>();
+    let file = std::fs::File::create(path)?;
+    serde_json::to_writer_pretty(std::io::BufWriter::new(file), &entries)?;
+    Ok(())
+}
+
+/// Writes the archive entries of every tweet that isn't a target, or removed in an earlier run
+/// according to `processed`, to a file in the format of `data/tweet.js`, so it can stand in for
+/// that file in an archive. Returns how many were written.
+fn write_remaining(
+    path: &Path,
+    tweets: &[(String, Value)],
+    targets: &[Target],
+    processed: &HashSet<String>,
+) -> std::io::Result<usize> {
+    let target_ids = targets
+        .iter()
+        .map(|t| t.id.as_str())
+        .collect::<HashSet<_>>();
+    let mut written = HashSet::new();
+    let entries = tweets
+        .iter()
+        .filter(|(id, _)| {
+            !target_ids.contains(id.as_str()) && !processed.contains(id) && written.insert(id)
+        })
+        .map(|(_, raw)| raw)
+        .collect::<Vec<_>>();
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    write!(file, "window.YTD.tweet.part0 = ")?;
+    serde_json::to_writer_pretty(&mut file, &entries)?;
+    writeln!(file)?;
+    file.flush()?;
+    Ok(entries.len())
+}
+
+/// Loads the state file, starting from an empty state if it doesn't exist yet.
+fn load_state(path: &Path) -> State {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return State::default(),
+        Err(e) => {
+            error!("🚨 Could not read state file {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    };
+    match serde_json::from_str(&contents) {
+        Ok(state) => state,
+        Err(e) => {
+            error!("🚨 Could not parse state file {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Writes a file, replacing the previous one atomically so a crash mid-write can't corrupt it.
+fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let temp_path = path.with_extension("tmp");
+    std::fs::write(&temp_path, contents)?;
+    std::fs::rename(&temp_path, path)
+}
+
+/// Writes the state file.
+fn save_state(path: &Path, state: &State) -> std::io::Result<()> {
+    write_atomically(path, serde_json::to_string(state)?.as_bytes())
+}
+
+/// Reads a file written by --export-state.
+fn load_bundle(path: &Path) -> Result<StateBundle, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&contents).map_err(|e| e.to_string())
+}
+
+/// Writes the file for --export-state.
+fn save_bundle(path: &Path, bundle: &StateBundle) -> std::io::Result<()> {
+    write_atomically(path, serde_json::to_string(bundle)?.as_bytes())
+}
+
+/// Totals across every run that actually deleted something, kept in the stats file.
+#[derive(Serialize, Deserialize, Default)]
+struct LifetimeStats {
+    runs: usize,
+    /// Tweets deleted, likes removed, and messages deleted, not counting ones that were already
+    /// gone.
+    deleted: usize,
+    failed: usize,
+    last_run: Option<DateTime<Utc>>,
+}
+
+/// Loads the stats file, starting from zero if there isn't one yet.
+fn load_stats(path: &Path) -> Result<LifetimeStats, String> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).map_err(|e| e.to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(LifetimeStats::default()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Adds a finished run to the stats file.
+fn record_stats(path: &Path, summary: &Summary) -> Result<(), String> {
+    let mut stats = load_stats(path)?;
+    stats.runs += 1;
+    stats.deleted += summary.deleted - summary.already_deleted;
+    stats.failed += summary.failed;
+    stats.last_run = Some(Utc::now());
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let contents = serde_json::to_vec(&stats).map_err(|e| e.to_string())?;
+    write_atomically(path, &contents).map_err(|e| e.to_string())
+}
+
+/// Prints the stats file, for `damae stats`.
+fn print_stats() {
+    let path = match stats_path() {
+        Some(path) => path,
+        None => {
+            eprintln!("🚨 No data directory available to keep stats in");
+            std::process::exit(1);
+        }
+    };
+    let stats = match load_stats(&path) {
+        Ok(stats) => stats,
+        Err(e) => {
+            eprintln!("🚨 Could not read stats file {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    };
+    match stats.last_run {
+        Some(last_run) => {
+            println!("📊 Runs: {}", stats.runs);
+            println!("🗑️ Deleted: {}", stats.deleted);
+            println!("🚨 Failed: {}", stats.failed);
+            println!(
+                "🕰️ Last run: {}",
+                last_run.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+            );
+        }
+        None => println!("📊 Nothing has been deleted yet"),
+    }
+}
+
+/// Downloads a file, following redirects.
+async fn download(url: &str) -> Result<hyper::body::Bytes, String> {
+    let mut url = url.to_string();
+    for _ in 0..5 {
+        let uri = url.parse::<hyper::Uri>().map_err(|e| e.to_string())?;
+        let response = net::client().get(uri).await.map_err(|e| e.to_string())?;
+        if response.status().is_redirection() {
+            url = response
+                .headers()
+                .get(hyper::header::LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .ok_or("redirect without a location")?
+                .to_string();
+            continue;
+        }
+        if !response.status().is_success() {
+            return Err(format!("{} returned {}", url, response.status()));
+        }
+        return hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(|e| e.to_string());
+    }
+    Err(format!("too many redirects for {}", url))
+}
+
+/// Returns the name a media item is saved under, the last part of its URL.
+fn media_file_name(url: &str) -> Result<&str, String> {
+    url.split('?')
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| format!("can't determine a file name for {}", url))
+}
+
+/// Undoes the escaping of `&`, `<`, and `>` in the text of tweets in the archive.
+fn unescape_html(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Writes one tweet of a Markdown export, downloading its media into `<dir>/media/<id>/` and
+/// linking to the original URL instead if that fails.
+async fn write_markdown_tweet(markdown: &mut String, dir: &Path, target: &Target, username: &str) {
+    let date = target.created_at.map_or_else(
+        || "Unknown date".to_string(),
+        |c| c.format("%Y-%m-%d %H:%M UTC").to_string(),
+    );
+    markdown.push_str(&format!(
+        "[{}](https://twitter.com/{}/status/{})\n\n",
+        date, username, target.id
+    ));
+    if let Some(text) = &target.text {
+        markdown.push_str(&unescape_html(text));
+        markdown.push_str("\n\n");
+    }
+    for url in &target.media_urls {
+        let link = match media_file_name(url) {
+            Ok(file_name) => {
+                let media_dir = dir.join("media").join(&target.id);
+                let saved = match download(url).await {
+                    Ok(bytes) => tokio::fs::create_dir_all(&media_dir)
+                        .await
+                        .and(tokio::fs::write(media_dir.join(file_name), bytes).await)
+                        .map_err(|e| e.to_string()),
+                    Err(e) => Err(e),
+                };
+                match saved {
+                    Ok(_) => format!("media/{}/{}", target.id, file_name),
+                    Err(e) => {
+                        warn!("🚨 Failed to save media for {}: {}", target.id, e);
+                        url.clone()
+                    }
+                }
+            }
+            Err(_) => url.clone(),
+        };
+        markdown.push_str(&format!("![]({})\n\n", link));
+    }
+}
+
+/// Writes the tweets about to be deleted to `dir` as Markdown files, for
+/// --export-thread-markdown. Each self-thread goes in one file named after its first tweet, with
+/// its tweets in the order they were posted, and every other tweet gets a file of its own.
+/// Retweets are left out, since there's nothing of the account's own to keep. Returns how many
+/// files were written.
+async fn export_markdown(
+    dir: &Path,
+    targets: &[Target],
+    threads: &ThreadIndex,
+    username: &str,
+) -> std::io::Result<usize> {
+    let roots = threads.self_thread_roots();
+    let mut files = BTreeMap::<&str, Vec<&Target>>::new();
+    for target in targets.iter().filter(|t| t.retweeted_status_id.is_none()) {
+        let root = roots
+            .get(target.id.as_str())
+            .copied()
+            .unwrap_or(target.id.as_str());
+        files.entry(root).or_default().push(target);
+    }
+    std::fs::create_dir_all(dir)?;
+    let written = files.len();
+    for (root, mut tweets) in files {
+        tweets.sort_by_key(|t| (t.created_at, t.id.parse::<u64>().ok()));
+        let mut markdown = String::new();
+        if let Some(created_at) = tweets[0].created_at {
+            markdown.push_str(&format!("---\ndate: {}\n---\n\n", created_at.to_rfc3339()));
+        }
+        for (i, target) in tweets.iter().enumerate() {
+            if i > 0 {
+                markdown.push_str("---\n\n");
+            }
+            write_markdown_tweet(&mut markdown, dir, target, username).await;
+        }
+        std::fs::write(dir.join(format!("{}.md", root)), markdown)?;
+    }
+    Ok(written)
+}
+
+/// Saves every media item attached to a target into a directory named after its ID.
+async fn save_media(dir: &Path, target: &Target) -> Result<(), String> {
+    let dir = dir.join(&target.id);
+    for url in &target.media_urls {
+        let file_name = media_file_name(url)?;
+        let bytes = download(url).await?;
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| e.to_string())?;
+        tokio::fs::write(dir.join(file_name), bytes)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Counts of how the items fared, reported once the run finishes.
+pub struct Summary {
+    /// How many items were deleted, or would have been in a dry run.
+    pub deleted: usize,
+    /// How many of the deleted items turned out to be deleted already.
+    pub already_deleted: usize,
+    /// How many items couldn't be deleted.
+    pub failed: usize,
+    /// How many times each twitter error code caused a deletion to fail.
+    pub error_codes: BTreeMap<i32, usize>,
+    /// How long the run took.
+    pub elapsed: Duration,
+    /// How many items were skipped in interactive mode.
+    pub skipped: usize,
+    /// How many items were never processed because the run was stopped early, with Ctrl-C or
+    /// by quitting in interactive mode.
+    pub remaining: usize,
+    /// How many items were deleted from each calendar year.
+    pub by_year: BTreeMap<i32, usize>,
+    /// The IDs of every item that was deleted, or found to be deleted already.
+    pub removed_ids: Vec<String>,
+    /// The IDs of every item that couldn't be deleted, except the ones that turned out to be
+    /// gone already.
+    pub failed_ids: Vec<String>,
+}
+
+/// Counts the items created in each calendar year, skipping items without a creation date.
+fn count_by_year<'a>(
+    dates: impl IntoIterator<Item = &'a DateTime<Utc>>,
+    timezone: Option<Tz>,
+) -> BTreeMap<i32, usize> {
+    let mut by_year = BTreeMap::new();
+    for date in dates {
+        *by_year.entry(year_in(timezone, date)).or_insert(0) += 1;
+    }
+    by_year
+}
+
+/// Picks `fraction` of the targets at random for --sample-fraction, keeping them in order.
+///
+/// Either way, round(fraction × n) of the n targets are picked. `Uniform` picks them with every
+/// set of that many targets equally likely, so a month with few tweets can lose far more or
+/// fewer than its share by chance. `AgeWeighted` is a stratified sample instead: the targets are
+/// split by the month they were posted in (undated ones together), and from a month with m
+/// targets it picks ⌊fraction × m⌋ of them, chosen uniformly within the month. That leaves a few
+/// tweets to reach the total, which go one each to the months with the largest remainders
+/// fraction × m − ⌊fraction × m⌋ (ties broken at random), so every month loses its share
+/// rounded up or down.
+fn sample(
+    targets: Vec<Target>,
+    fraction: f64,
+    strategy: SampleStrategy,
+    timezone: Option<Tz>,
+    rng: &mut impl Rng,
+) -> Vec<Target> {
+    let total = (fraction * targets.len() as f64).round() as usize;
+    let mut strata = BTreeMap::<_, Vec<usize>>::new();
+    for (i, target) in targets.iter().enumerate() {
+        let month = match strategy {
+            SampleStrategy::Uniform => None,
+            SampleStrategy::AgeWeighted => target
+                .created_at
+                .map(|created_at| month_in(timezone, &created_at)),
+        };
+        strata.entry(month).or_default().push(i);
+    }
+    let strata = strata.into_values().collect::<Vec<_>>();
+    let mut counts = strata
+        .iter()
+        .map(|stratum| (fraction * stratum.len() as f64).floor() as usize)
+        .collect::<Vec<_>>();
+    let extra = total.saturating_sub(counts.iter().sum());
+    let mut by_remainder = (0..strata.len()).collect::<Vec<_>>();
+    by_remainder.shuffle(rng);
+    let remainder = |i: usize| fraction * strata[i].len() as f64 - counts[i] as f64;
+    by_remainder.sort_by(|&a, &b| remainder(b).total_cmp(&remainder(a)));
+    for i in by_remainder.into_iter().take(extra) {
+        counts[i] += 1;
+    }
+    let mut picked = vec![false; targets.len()];
+    for (stratum, count) in strata.iter().zip(counts) {
+        for &i in stratum.choose_multiple(rng, count) {
+            picked[i] = true;
+        }
+    }
+    targets
+        .into_iter()
+        .zip(picked)
+        .filter_map(|(target, picked)| picked.then_some(target))
+        .collect()
+}
+
+/// Returns the calendar year and month of a date in the given time zone, or the system time zone
+/// if there isn't one.
+fn month_in(timezone: Option<Tz>, date: &DateTime<Utc>) -> (i32, u32) {
+    match timezone {
+        Some(tz) => {
+            let date = date.with_timezone(&tz);
+            (date.year(), date.month())
+        }
+        None => {
+            let date = date.with_timezone(&Local);
+            (date.year(), date.month())
+        }
+    }
+}
+
+/// Returns the calendar year of a date in the given time zone, or the system time zone if there
+/// isn't one, so that tweets posted around New Year's fall in the year the user expects.
+fn year_in(timezone: Option<Tz>, date: &DateTime<Utc>) -> i32 {
+    match timezone {
+        Some(tz) => date.with_timezone(&tz).year(),
+        None => date.with_timezone(&Local).year(),
+    }
+}
+
+/// Prints a table of how many items fall in each calendar year, with a bar for each.
+fn print_year_histogram(by_year: &BTreeMap<i32, usize>) {
+    const BAR_WIDTH: usize = 40;
+    let max = by_year.values().copied().max().unwrap_or(0);
+    for (year, count) in by_year {
+        let bar = "█".repeat((count * BAR_WIDTH).div_ceil(max.max(1)));
+        info!("  {} {:>7} {}", year, count, bar);
+    }
+}
+
+/// What to do with a single item in interactive mode.
+#[derive(Clone, Copy, PartialEq)]
+enum Choice {
+    Delete,
+    Skip,
+    Quit,
+}
+
+/// How many of the oldest, and of the newest, items are shown before the confirmation prompt.
+const SAMPLE_SIZE: usize = 5;
+
+/// Prints the oldest and newest items that are about to be removed, so mistakes in the filters
+/// can be caught before confirming. Items without a date are left out.
+fn print_sample(targets: &[Target], mode: Mode, text_width: usize) {
+    let mut dated = targets
+        .iter()
+        .filter(|t| t.created_at.is_some())
+        .collect::<Vec<_>>();
+    dated.sort_by_key(|t| t.created_at);
+    let print = |target: &Target| {
+        info!(
+            "    {} {}: {}",
+            target.created_at.unwrap().format("%Y-%m-%d"),
+            target.id,
+            truncate_display(target.text.as_deref().unwrap_or(""), text_width)
+        )
+    };
+    if dated.is_empty() {
+        return;
+    }
+    if dated.len() <= 2 * SAMPLE_SIZE {
+        info!("📋 The {} {} to {}:", dated.len(), mode.noun(), mode.verb());
+        dated.into_iter().for_each(print);
+        return;
+    }
+    info!(
+        "📋 The {} oldest {} to {}:",
+        SAMPLE_SIZE,
+        mode.noun(),
+        mode.verb()
+    );
+    dated[..SAMPLE_SIZE].iter().copied().for_each(print);
+    info!("📋 The {} newest:", SAMPLE_SIZE);
+    dated[dated.len() - SAMPLE_SIZE..]
+        .iter()
+        .copied()
+        .for_each(print);
+}
+
+/// Shows an item and asks whether to delete it. Any failure to read the answer is treated as a
+/// request to quit.
+fn prompt_for(target: &Target, mode: Mode) -> Choice {
+    let date = target
+        .created_at
+        .map(|created_at| created_at.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "unknown date".to_string());
+    eprintln!();
+    eprintln!("{} ({})", target.id, date);
+    eprintln!(
+        "{}",
+        target.text.as_deref().unwrap_or("(no text in the archive)")
+    );
+    let mut verb = mode.verb().to_string();
+    verb[..1].make_ascii_uppercase();
+    let choices = [Choice::Delete, Choice::Skip, Choice::Quit];
+    dialoguer::Select::new()
+        .items(&[verb.as_str(), "Skip", "Quit"])
+        .default(1)
+        .interact_opt()
+        .ok()
+        .flatten()
+        .map_or(Choice::Quit, |i| choices[i])
+}
+
+/// The summary printed to stdout with --json-report.
+#[derive(Serialize)]
+struct Report<'a> {
+    considered: usize,
+    deleted: usize,
+    already_deleted: usize,
+    failed: usize,
+    skipped: usize,
+    not_processed: usize,
+    dry_run: bool,
+    elapsed_seconds: f64,
+    error_codes: &'a BTreeMap<i32, usize>,
+}
+
+impl<'a> Report<'a> {
+    fn new(considered: usize, summary: &'a Summary, opts: &Opts) -> Self {
+        Report {
+            considered,
+            deleted: summary.deleted - summary.already_deleted,
+            already_deleted: summary.already_deleted,
+            failed: summary.failed,
+            skipped: summary.skipped,
+            not_processed: summary.remaining,
+            dry_run: opts.common.dry_run,
+            elapsed_seconds: summary.elapsed.as_secs_f64(),
+            error_codes: &summary.error_codes,
+        }
+    }
+}
+
+/// Escapes text for use in HTML.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The styles for --report-html, inlined so the page works on its own.
+const REPORT_CSS: &str = "body{font-family:-apple-system,'Segoe UI',Helvetica,Arial,sans-serif;max-width:720px;margin:2em auto;padding:0 1em;color:#14171a}
+h1{font-size:1.5em}h2{font-size:1.1em;margin-top:2em}
+table{border-collapse:collapse;width:100%}td,th{padding:.3em .5em;text-align:left;border-bottom:1px solid #e1e8ed}
+td.count{text-align:right;font-variant-numeric:tabular-nums;width:5em}
+.bar{background:#1da1f2;height:1em;border-radius:2px}
+pre{background:#f5f8fa;padding:1em;overflow-x:auto}
+.note{color:#657786}";
+
+/// Builds the page written by --report-html, from the same summary as --json-report along with
+/// the breakdown by year and the settings the run used. Credentials are left out, so the page can
+/// be shared.
+fn html_report(report: &Report, summary: &Summary, opts: &Opts, username: &str) -> String {
+    let mut settings = effective_config(opts);
+    settings.consumer_key = None;
+    settings.consumer_secret = None;
+    settings.access_token = None;
+    settings.access_token_secret = None;
+    let past_tense = opts.mode.past_tense();
+    let mut rows = vec![
+        ("Selected".to_string(), report.considered.to_string()),
+        (
+            if report.dry_run {
+                format!("Would be {}", past_tense.to_lowercase())
+            } else {
+                past_tense.to_string()
+            },
+            report.deleted.to_string(),
+        ),
+        (
+            "Already gone".to_string(),
+            report.already_deleted.to_string(),
+        ),
+        ("Failed".to_string(), report.failed.to_string()),
+        ("Skipped".to_string(), report.skipped.to_string()),
+        (
+            "Not processed".to_string(),
+            report.not_processed.to_string(),
+        ),
+        (
+            "Time taken".to_string(),
+            HumanDuration(summary.elapsed).to_string(),
+        ),
+    ];
+    if !report.error_codes.is_empty() {
+        rows.push((
+            "Errors by code".to_string(),
+            format_error_codes(report.error_codes),
+        ));
+    }
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Damae report for @{user}</title>\n<style>\n{css}\n</style>\n</head>\n<body>\n<h1>Damae report for @{user}</h1>\n<p class=\"note\">{run} on {date}</p>\n<h2>Summary</h2>\n<table>\n",
+        user = escape_html(username),
+        css = REPORT_CSS,
+        run = if report.dry_run {
+            format!("Dry run for {}, nothing was changed,", opts.mode.noun())
+        } else {
+            format!("{} {}", past_tense, opts.mode.noun())
+        },
+        date = Local::now().format("%Y-%m-%d %H:%M"),
+    );
+    for (label, value) in rows {
+        html.push_str(&format!(
+            "<tr><th>{}</th><td>{}</td></tr>\n",
+            escape_html(&label),
+            escape_html(&value)
+        ));
+    }
+    html.push_str("</table>\n");
+    if !summary.by_year.is_empty() {
+        html.push_str("<h2>By year</h2>\n<table>\n");
+        let max = summary.by_year.values().copied().max().unwrap_or(0).max(1);
+        for (year, count) in &summary.by_year {
+            html.push_str(&format!(
+                "<tr><th>{}</th><td class=\"count\">{}</td><td><div class=\"bar\" style=\"width:{:.1}%\"></div></td></tr>\n",
+                year,
+                count,
+                *count as f64 * 100.0 / max as f64
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+    html.push_str(&format!(
+        "<h2>Settings</h2>\n<pre>{}</pre>\n</body>\n</html>\n",
+        escape_html(&toml::to_string(&settings).unwrap())
+    ));
+    html
+}
+
+/// How many deletions --verify-after checks when --verify-sample isn't given.
+const VERIFY_CAP: usize = 1000;
+
+/// Whether an item that was reported as deleted is really gone.
+enum Verification {
+    Gone,
+    StillThere,
+    Unknown(egg_mode::error::Error),
+}
+
+/// Asks twitter whether a deleted tweet, or a removed like, still exists.
+async fn verify_removed(mode: Mode, id: &str, token: &egg_mode::Token) -> Verification {
+    #[derive(Deserialize)]
+    struct ShownTweet {
+        favorited: Option<bool>,
+    }
+    let request = egg_mode::raw::request_get(
+        "https://api.twitter.com/1.1/statuses/show.json",
+        token,
+        Some(&egg_mode::raw::ParamList::new().add_param("id", id.to_string())),
+    );
+    match net::send_json::<ShownTweet>(request).await {
+        Ok(tweet) => match mode {
+            Mode::Likes if tweet.favorited != Some(true) => Verification::Gone,
+            _ => Verification::StillThere,
+        },
+        Err(egg_mode::error::Error::TwitterError(_, te))
+            if te.errors.iter().any(|ec| matches!(ec.code, 34 | 144)) =>
+        {
+            Verification::Gone
+        }
+        Err(e) => Verification::Unknown(e),
+    }
+}
+
+/// Checks a sample of the removed items with twitter and reports any that are still there.
+async fn verify(removed_ids: &[String], token: &egg_mode::Token, opts: &Opts) {
+    let sample_size = opts
+        .common
+        .verify_sample
+        .unwrap_or(VERIFY_CAP)
+        .min(removed_ids.len());
+    let sample = removed_ids.choose_multiple(&mut rand::thread_rng(), sample_size);
+    info!("🔎 Verifying {} {}", sample_size, opts.mode.noun());
+    let results = futures::stream::iter(
+        sample.map(|id| async move { (id, verify_removed(opts.mode, id, token).await) }),
+    )
+    .buffer_unordered(opts.common.max_tasks)
+    .collect::<Vec<_>>()
+    .await;
+    let mut still_there = 0;
+    let mut unknown = 0;
+    for (id, result) in results {
+        match result {
+            Verification::Gone => {}
+            Verification::StillThere => {
+                still_there += 1;
+                warn!(
+                    "👻 {} is still there after being {}",
+                    id,
+                    opts.mode.past_tense().to_lowercase()
+                );
+            }
+            Verification::Unknown(e) => {
+                unknown += 1;
+                warn!("⚠️ Could not verify {}: {}", id, e);
+            }
+        }
+    }
+    if still_there == 0 && unknown == 0 {
+        info!(
+            "🔎 Verified that all {} checked {} are gone",
+            sample_size,
+            opts.mode.noun()
+        );
+    } else {
+        warn!(
+            "🔎 Verification: {} of {} checked {} still exist, {} could not be checked",
+            still_there,
+            sample_size,
+            opts.mode.noun(),
+            unknown
+        );
+    }
+}
+
+impl CommonOpts {
+    /// Returns how progress should be shown, falling back to plain text when the output is going
+    /// to a file or another program rather than a terminal.
+    fn progress(&self) -> Progress {
+        self.progress_style.unwrap_or_else(|| {
+            if std::io::stdout().is_terminal() && std::io::stderr().is_terminal() {
+                Progress::Bar
+            } else {
+                Progress::Plain
+            }
+        })
+    }
+}
+
+/// How often --progress-style plain prints the progress of the run.
+const PLAIN_PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Prints how many items have been processed every few seconds until it's cancelled, for
+/// --progress-style plain.
+async fn print_progress(
+    pb: ProgressBar,
+    total: usize,
+    rate_limit_reset: Arc<Mutex<Option<i32>>>,
+    mode: Mode,
+) {
+    let mut interval = tokio::time::interval(PLAIN_PROGRESS_INTERVAL);
+    // The first tick completes immediately, when nothing has been processed yet.
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let processed = pb.position();
+        let rate_limited = match *rate_limit_reset.lock().await {
+            Some(reset) if i64::from(reset) > Utc::now().timestamp() => format!(
+                ", rate limited until {}",
+                Utc.timestamp(i64::from(reset), 0)
+                    .with_timezone(&Local)
+                    .format("%H:%M:%S")
+            ),
+            _ => String::new(),
+        };
+        info!(
+            "📊 Processed {}/{} {}{}",
+            processed,
+            total,
+            mode.noun(),
+            rate_limited
+        );
+    }
+}
+
+/// Exit status used when the run finished but some deletions failed, distinct from the 1 used
+/// when the run can't start at all.
+const EXIT_FAILURES: i32 = 2;
+
+/// Asks the run to stop on the first Ctrl-C, letting deletions that are already in flight
+/// finish, and exits immediately on the second.
+async fn handle_ctrl_c(stop: Arc<AtomicBool>) {
+    if tokio::signal::ctrl_c().await.is_err() {
+        return;
+    }
+    stop.store(true, Ordering::SeqCst);
+    warn!("🛑 Stopping after the deletions in progress finish, press Ctrl-C again to quit now");
+    if tokio::signal::ctrl_c().await.is_ok() {
+        tui::restore();
+        std::process::exit(130);
+    }
+}
+
+/// How often a pause between batches checks whether the run was stopped.
+const BATCH_PAUSE_POLL: Duration = Duration::from_millis(100);
+
+/// Waits out the --batch-delay, returning early once the run is stopped so Ctrl-C doesn't have
+/// to wait for it.
+async fn pause_between_batches(delay: Duration, stop: &AtomicBool, pb: &ProgressBar) {
+    let resume_at = chrono::Local::now() + chrono::Duration::from_std(delay).unwrap();
+    pb.set_message(format!(
+        "⏸️ Waiting between batches, resuming at {}",
+        resume_at.format("%H:%M:%S")
+    ));
+    debug!("⏸️ Waiting {} before the next batch", HumanDuration(delay));
+    let deadline = tokio::time::Instant::now() + delay;
+    while !stop.load(Ordering::SeqCst) && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(
+            BATCH_PAUSE_POLL.min(deadline.saturating_duration_since(tokio::time::Instant::now())),
+        )
+        .await;
+    }
+    pb.set_message("");
+}
+
+/// Stops the run once the --max-runtime deadline passes.
+async fn stop_after(max_runtime: Duration, stop: Arc<AtomicBool>) {
+    tokio::time::sleep(max_runtime).await;
+    if !stop.swap(true, Ordering::SeqCst) {
+        warn!(
+            "🛑 Reached the maximum runtime of {}, stopping after the deletions in progress finish",
+            HumanDuration(max_runtime)
+        );
+    }
+}
+
+/// Creates a file the run writes its results to, exiting if that's not possible.
+fn create_or_exit(path: &Path, description: &str) -> std::fs::File {
+    std::fs::File::create(path).unwrap_or_else(|e| {
+        error!(
+            "🚨 Could not create {} {}: {}",
+            description,
+            path.display(),
+            e
+        );
+        std::process::exit(1);
+    })
+}
+
+/// Asks whether to go ahead with deleting `count` items from the account. With a
+/// `--confirm-phrase`, the handle or the count has to be typed out instead of answering y/N, and
+/// an empty answer aborts.
+fn confirm_run(opts: &Opts, count: usize, screen_name: &str) -> bool {
+    let phrase = opts.common.confirm_phrase;
+    let prompt = if !opts.tweets.redact.is_empty() {
+        format!(
+            "This will post redacted copies of up to {} tweets to @{} and delete the originals \
+             permanently",
+            count, screen_name
+        )
+    } else if opts.mode.is_permanent() {
+        format!(
+            "This will {} up to {} {} from @{} permanently",
+            opts.mode.verb(),
+            count,
+            opts.mode.noun(),
+            screen_name
+        )
+    } else {
+        format!(
+            "This will {} up to {} {} for @{}",
+            opts.mode.verb(),
+            count,
+            opts.mode.noun(),
+            screen_name
+        )
+    };
+    let expected = match phrase {
+        None => {
+            return Confirm::new()
+                .with_prompt(if opts.mode.is_permanent() {
+                    format!("{}, are you sure you want to continue?", prompt)
+                } else {
+                    format!("{}, do you want to continue?", prompt)
+                })
+                .default(false)
+                .interact()
+                .unwrap()
+        }
+        Some(ConfirmPhrase::Handle) => format!("@{}", screen_name),
+        Some(ConfirmPhrase::Count) => count.to_string(),
+    };
+    // Handles aren't case sensitive, and the @ is optional.
+    let matches = |input: &str| {
+        let input = input.trim();
+        match phrase {
+            Some(ConfirmPhrase::Handle) => input
+                .trim_start_matches('@')
+                .eq_ignore_ascii_case(screen_name),
+            _ => input == expected,
+        }
+    };
+    let input: String = Input::new()
+        .with_prompt(format!(
+            "{}, type {} to continue (or nothing to abort)",
+            prompt, expected
+        ))
+        .allow_empty(true)
+        .validate_with(|input: &String| {
+            if input.trim().is_empty() || matches(input) {
+                Ok(())
+            } else {
+                Err(format!("That isn't {}", expected))
+            }
+        })
+        .interact_text()
+        .unwrap();
+    !input.trim().is_empty()
+}
+
+/// Performs the action for the selected mode on every target, with bounded concurrency,
+/// progress reporting, logging and checkpointing. Setting `stop` stops the run once the
+/// deletions in flight finish.
+async fn run(
+    targets: &[Target],
+    token: &egg_mode::Token,
+    user_id: u64,
+    opts: &Opts,
+    state: State,
+    stop: Arc<AtomicBool>,
+) -> Summary {
+    let started = Instant::now();
+    let progress = opts.common.progress();
+    let show_dashboard = opts.common.tui
+        && !opts.common.interactive
+        && match tui::unavailable() {
+            None => true,
+            Some(reason) => {
+                warn!("⚠️ {}, showing the progress bar instead of --tui", reason);
+                false
+            }
+        };
+    // The progress bar would draw over the prompts in interactive mode. The dashboard draws its
+    // own, from the same progress.
+    let pb = if show_dashboard {
+        ProgressBar::with_draw_target(targets.len() as u64, ProgressDrawTarget::hidden())
+    } else if opts.common.interactive || progress != Progress::Bar {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(targets.len() as u64)
+    };
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{elapsed_precise} {wide_bar} {pos}/{len} ({per_sec}, ETA {eta}) {msg}"),
+    );
+    let failed = Arc::new(AtomicUsize::new(0));
+    let deleted = Arc::new(AtomicUsize::new(0));
+    let already_deleted = Arc::new(AtomicUsize::new(0));
+    let error_codes = Arc::new(Mutex::new(BTreeMap::new()));
+    let removed_ids = Arc::new(Mutex::new(Vec::new()));
+    let failed_ids = Arc::new(Mutex::new(Vec::new()));
+    let by_year = Arc::new(Mutex::new(BTreeMap::new()));
+    let skipped = Arc::new(AtomicUsize::new(0));
+    let remaining = Arc::new(AtomicUsize::new(0));
+    let recent_failures = Arc::new(Mutex::new(VecDeque::new()));
+    let account_failed = Arc::new(AtomicBool::new(false));
+    if let Some(secs) = opts.common.max_runtime {
+        tokio::spawn(stop_after(Duration::from_secs(secs), stop.clone()));
+    }
+    let rate_limit_reset = Arc::new(Mutex::new(None));
+    let rate_limit_waits = Arc::new(AtomicUsize::new(0));
+    let metrics_server = opts.common.metrics_addr.map(|addr| {
+        let metrics = metrics::Metrics {
+            total: targets.len(),
+            deleted: deleted.clone(),
+            failed: failed.clone(),
+            rate_limit_waits: rate_limit_waits.clone(),
+        };
+        match metrics::serve(addr, metrics) {
+            Ok(server) => {
+                info!("📈 Serving metrics on http://{}/metrics", addr);
+                server
+            }
+            Err(e) => {
+                error!("🚨 Could not start the metrics server: {}", e);
+                std::process::exit(1);
+            }
+        }
+    });
+    let throttle = Arc::new(Throttle::new(opts.common.rate_limit));
+    let concurrency = Arc::new(Concurrency::new(opts));
+    let ticker =
+        (progress == Progress::Plain && !opts.common.interactive && !show_dashboard).then(|| {
+            tokio::spawn(print_progress(
+                pb.clone(),
+                targets.len(),
+                rate_limit_reset.clone(),
+                opts.mode,
+            ))
+        });
+    let state = Arc::new(Mutex::new(state));
+    let log_file = Arc::new(Mutex::new(
+        opts.common
+            .log_file
+            .as_ref()
+            .map(|path| create_or_exit(path, "log file")),
+    ));
+    // Written as failures happen rather than at the end, so the IDs are kept even if the run is
+    // killed.
+    let failed_ids_file = Arc::new(Mutex::new(
+        opts.common
+            .save_failed_ids
+            .as_ref()
+            .map(|path| create_or_exit(path, "failed IDs file")),
+    ));
+    let dashboard = show_dashboard.then(|| tui::Counters {
+        title: format!("Damae: {} {}", opts.mode.verb(), opts.mode.noun()),
+        pb: pb.clone(),
+        deleted: deleted.clone(),
+        already_deleted: already_deleted.clone(),
+        failed: failed.clone(),
+        error_codes: error_codes.clone(),
+        recent_failures: recent_failures.clone(),
+        rate_limit_reset: rate_limit_reset.clone(),
+        stop: stop.clone(),
+    });
+    let dashboard = dashboard.and_then(|counters| match tui::start(counters) {
+        Ok(dashboard) => Some(dashboard),
+        Err(e) => {
+            warn!("⚠️ Could not show the --tui dashboard: {}", e);
+            None
+        }
+    });
+    let process = |target| {
+        let failed = failed.clone();
+        let recent_failures = recent_failures.clone();
+        let deleted = deleted.clone();
+        let already_deleted = already_deleted.clone();
+        let error_codes = error_codes.clone();
+        let removed_ids = removed_ids.clone();
+        let failed_ids = failed_ids.clone();
+        let by_year = by_year.clone();
+        let skipped = skipped.clone();
+        let remaining = remaining.clone();
+        let stop = stop.clone();
+        let account_failed = account_failed.clone();
+        let rate_limit_reset = rate_limit_reset.clone();
+        let rate_limit_waits = rate_limit_waits.clone();
+        let throttle = throttle.clone();
+        let concurrency = concurrency.clone();
+        let log_file = log_file.clone();
+        let failed_ids_file = failed_ids_file.clone();
+        let state = state.clone();
+        let pb = pb.clone();
+        async move {
+            if stop.load(Ordering::SeqCst) {
+                remaining.fetch_add(1, Ordering::SeqCst);
+                return;
+            }
+            if opts.common.interactive {
+                // Tasks run one at a time in interactive mode, so blocking here is fine.
+                match tokio::task::block_in_place(|| prompt_for(target, opts.mode)) {
+                    Choice::Delete => {}
+                    Choice::Skip => {
+                        skipped.fetch_add(1, Ordering::SeqCst);
+                        return;
+                    }
+                    Choice::Quit => {
+                        stop.store(true, Ordering::SeqCst);
+                        remaining.fetch_add(1, Ordering::SeqCst);
+                        return;
+                    }
+                }
+            }
+            let media_error = match &opts.common.save_media {
+                Some(dir) if !target.media_urls.is_empty() => save_media(dir, target).await.err(),
+                _ => None,
+            };
+            if let Some(e) = &media_error {
+                warn!("🚨 Failed to save media for {}: {}", target.id, e);
+            }
+            // The redacted copy is posted first, so the text is never lost if deleting fails. It's
+            // only held back when the tweet is kept anyway because its media couldn't be saved.
+            let keep_for_media = media_error.is_some() && opts.common.require_media_saved;
+            let repost_error = match &target.repost {
+                Some(repost) if !opts.common.dry_run && !keep_for_media => match post_repost(
+                    repost,
+                    token,
+                    opts,
+                    &rate_limit_reset,
+                    &rate_limit_waits,
+                    &pb,
+                )
+                .await
+                {
+                    Ok(id) => {
+                        info!("✂️ Posted {} as the redacted copy of {}", id, target.id);
+                        None
+                    }
+                    Err(e) => Some(e),
+                },
+                _ => None,
+            };
+            // Whether the failure was because the tweet is already gone, which retrying won't fix.
+            let mut missing = false;
+            let (status, error) =
+                if let (Some(e), true) = (media_error, opts.common.require_media_saved) {
+                    failed.fetch_add(1, Ordering::SeqCst);
+                    (
+                        Status::Failed,
+                        Some(format!("media could not be saved: {}", e)),
+                    )
+                } else if let Some(e) = repost_error {
+                    failed.fetch_add(1, Ordering::SeqCst);
+                    warn!(
+                        "🚨 Failed to post the redacted copy of {}, keeping it: {}",
+                        target.id, e
+                    );
+                    (
+                        Status::Failed,
+                        Some(format!("the redacted copy could not be posted: {}", e)),
+                    )
+                } else if !opts.common.dry_run {
+                    match remove(
+                        target,
+                        token,
+                        user_id,
+                        opts,
+                        &rate_limit_reset,
+                        &rate_limit_waits,
+                        &throttle,
+                        &concurrency,
+                        &pb,
+                    )
+                    .await
+                    {
+                        Ok(deletion) => {
+                            debug!("{} {}", opts.mode.past_tense(), target.id);
+                            deleted.fetch_add(1, Ordering::SeqCst);
+                            match deletion {
+                                Deletion::Deleted => (Status::Deleted, None),
+                                Deletion::AlreadyDeleted => {
+                                    already_deleted.fetch_add(1, Ordering::SeqCst);
+                                    (Status::AlreadyDeleted, None)
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            failed.fetch_add(1, Ordering::SeqCst);
+                            let failure = classify_error(&e);
+                            missing = failure == Failure::AlreadyDeleted;
+                            let mut error_codes = error_codes.lock().await;
+                            for code in twitter_error_codes(&e) {
+                                *error_codes.entry(code).or_insert(0) += 1;
+                            }
+                            if let Failure::Account(code) = failure {
+                                // Reported once, instead of once for every deletion in flight.
+                                stop.store(true, Ordering::SeqCst);
+                                if !account_failed.swap(true, Ordering::SeqCst) {
+                                    error!(
+                                        "🚨 {} ({}), stopping the run",
+                                        describe_account_error(code),
+                                        e
+                                    );
+                                }
+                            } else {
+                                warn!("🚨 Failed to {} {}: {}", opts.mode.verb(), target.id, e);
+                            }
+                            (Status::Failed, Some(e.to_string()))
+                        }
+                    }
+                } else if let Some(repost) = &target.repost {
+                    deleted.fetch_add(1, Ordering::SeqCst);
+                    info!(
+                        "🥸 Would repost {} ({}) as: {}",
+                        target.id,
+                        target.created_at.map_or_else(
+                            || "unknown date".to_string(),
+                            |c| c.format("%Y-%m-%d").to_string()
+                        ),
+                        repost.text
+                    );
+                    (Status::DryRun, None)
+                } else {
+                    deleted.fetch_add(1, Ordering::SeqCst);
+                    info!(
+                        "🥸 Would {} {} ({}): {}",
+                        opts.mode.verb(),
+                        target.id,
+                        target.created_at.map_or_else(
+                            || "unknown date".to_string(),
+                            |c| c.format("%Y-%m-%d").to_string()
+                        ),
+                        truncate_display(
+                            target.text.as_deref().unwrap_or(""),
+                            opts.common.preview_text_width
+                        )
+                    );
+                    (Status::DryRun, None)
+                };
+            if let (Status::Failed, Some(error)) = (status, &error) {
+                let mut recent_failures = recent_failures.lock().await;
+                if recent_failures.len() == tui::RECENT_FAILURES {
+                    recent_failures.pop_front();
+                }
+                recent_failures.push_back(format!("{}: {}", target.id, error));
+            }
+            if opts.common.fail_fast
+                && status == Status::Failed
+                && !stop.swap(true, Ordering::SeqCst)
+            {
+                error!("🛑 Stopping after the first failure because of --fail-fast");
+            }
+            if let (Some(created_at), Status::Deleted | Status::AlreadyDeleted | Status::DryRun) =
+                (target.created_at, status)
+            {
+                *by_year
+                    .lock()
+                    .await
+                    .entry(year_in(opts.tweets.timezone, &created_at))
+                    .or_insert(0) += 1;
+            }
+            if let Status::Deleted | Status::AlreadyDeleted = status {
+                removed_ids.lock().await.push(target.id.clone());
+            }
+            if let (Some(path), Status::Deleted | Status::AlreadyDeleted) =
+                (&opts.common.state_file, status)
+            {
+                let mut state = state.lock().await;
+                if state.record(&target.id, opts.common.checkpoint_every) {
+                    if let Err(e) = save_state(path, &state) {
+                        error!("🚨 Failed to write state file: {}", e);
+                    }
+                }
+            }
+            if let (Status::Failed, false) = (status, missing) {
+                failed_ids.lock().await.push(target.id.clone());
+                if let Some(file) = failed_ids_file.lock().await.as_mut() {
+                    if let Err(e) = writeln!(file, "{}", target.id) {
+                        error!("🚨 Failed to write to failed IDs file: {}", e);
+                    }
+                }
+            }
+            if let Some(log_file) = log_file.lock().await.as_mut() {
+                let entry = LogEntry {
+                    id: &target.id,
+                    created_at: target.created_at,
+                    status,
+                    error,
+                    text: target.text.as_deref().filter(|_| status == Status::DryRun),
+                };
+                if let Err(e) = writeln!(log_file, "{}", serde_json::to_string(&entry).unwrap()) {
+                    error!("🚨 Failed to write to log file: {}", e);
+                }
+            }
+            pb.inc(1);
+        }
+    };
+    // Without --batch-size, everything is one batch.
+    let batch_size = opts.common.batch_size.unwrap_or(targets.len()).max(1);
+    for (i, batch) in targets.chunks(batch_size).enumerate() {
+        if let (Some(secs), true) = (opts.common.batch_delay, i > 0) {
+            pause_between_batches(Duration::from_secs(secs), &stop, &pb).await;
+        }
+        futures::stream::iter(batch.iter().map(&process))
+            .buffer_unordered(opts.common.max_tasks)
+            .collect::<Vec<_>>()
+            .await;
+    }
+    if let Some(ticker) = ticker {
+        ticker.abort();
+    }
+    if let Some(dashboard) = dashboard {
+        dashboard.finish().await;
+    }
+    if let Some(server) = metrics_server {
+        server.shutdown().await;
+    }
+    if stop.load(Ordering::SeqCst) {
+        pb.abandon();
+    } else {
+        pb.finish();
+    }
+    if let Some(path) = &opts.common.state_file {
+        if let Err(e) = save_state(path, &*state.lock().await) {
+            error!("🚨 Failed to write state file: {}", e);
+        }
+    }
+    let deleted = deleted.load(Ordering::SeqCst);
+    let already_deleted = already_deleted.load(Ordering::SeqCst);
+    let error_codes = std::mem::take(&mut *error_codes.lock().await);
+    let removed_ids = std::mem::take(&mut *removed_ids.lock().await);
+    let failed_ids = std::mem::take(&mut *failed_ids.lock().await);
+    let failed = failed.load(Ordering::SeqCst);
+    let by_year = std::mem::take(&mut *by_year.lock().await);
+    let skipped = skipped.load(Ordering::SeqCst);
+    let remaining = remaining.load(Ordering::SeqCst);
+    Summary {
+        deleted,
+        already_deleted,
+        failed,
+        error_codes,
+        elapsed: started.elapsed(),
+        skipped,
+        remaining,
+        by_year,
+        removed_ids,
+        failed_ids,
+    }
+}
+
+/// Returns a description of the prompt that will need to read from stdin during this run, if
+/// any.
+fn stdin_prompt(opts: &Opts) -> Option<&'static str> {
+    let has_token = opts.common.access_token.is_some() && opts.common.access_token_secret.is_some()
+        || !opts.common.no_cache
+            && token_cache_path()
+                .zip(opts.common.consumer_key.as_deref())
+                .and_then(|(path, key)| load_cached_token(&path, key))
+                .is_some();
+    if !opts.common.oauth2 && !has_token {
+        Some("the PIN login")
+    } else if opts.common.interactive {
+        Some("--interactive")
+    } else if !opts.common.yes && !opts.common.dry_run && !opts.common.summary_only {
+        Some("the confirmation prompt")
+    } else {
+        None
+    }
+}
+
+/// The account the tool is logged in as.
+#[derive(Deserialize)]
+struct User {
+    id: u64,
+    screen_name: String,
+}
+
+/// Reads the token from the URL-encoded body twitter returns during the PIN flow.
+fn parse_token_response(body: &[u8]) -> Result<egg_mode::KeyPair, egg_mode::error::Error> {
+    let mut key = None;
+    let mut secret = None;
+    for (name, value) in url::form_urlencoded::parse(body) {
+        match name.as_ref() {
+            "oauth_token" => key = Some(value.into_owned()),
+            "oauth_token_secret" => secret = Some(value.into_owned()),
+            _ => {}
+        }
+    }
+    Ok(egg_mode::KeyPair::new(
+        key.ok_or(egg_mode::error::Error::MissingValue("oauth_token"))?,
+        secret.ok_or(egg_mode::error::Error::MissingValue("oauth_token_secret"))?,
+    ))
+}
+
+/// Starts the PIN flow, like `egg_mode::auth::request_token` but through [`net::send`].
+async fn request_token(
+    con_token: &egg_mode::KeyPair,
+) -> Result<egg_mode::KeyPair, egg_mode::error::Error> {
+    let request = egg_mode::raw::auth::RequestBuilder::new(
+        egg_mode::raw::auth::Method::POST,
+        "https://api.twitter.com/oauth/request_token",
+    )
+    .oauth_callback("oob")
+    .request_keys(con_token, None);
+    let (_, body) = net::send(request).await?;
+    parse_token_response(&body)
+}
+
+/// Exchanges the PIN for an access token, like `egg_mode::auth::access_token` but through
+/// [`net::send`].
+async fn access_token(
+    con_token: egg_mode::KeyPair,
+    request_token: &egg_mode::KeyPair,
+    pin: &str,
+) -> Result<egg_mode::Token, egg_mode::error::Error> {
+    let request = egg_mode::raw::auth::RequestBuilder::new(
+        egg_mode::raw::auth::Method::POST,
+        "https://api.twitter.com/oauth/access_token",
+    )
+    .oauth_verifier(pin)
+    .request_keys(&con_token, Some(request_token));
+    let (_, body) = net::send(request).await?;
+    Ok(egg_mode::Token::Access {
+        consumer: con_token,
+        access: parse_token_response(&body)?,
+    })
+}
+
+/// Gets an OAuth 1.0a access token, from the command line, the cache, or by asking the user to
+/// authorize the app and enter the PIN twitter shows them.
+async fn pin_token(opts: &Opts, token_cache_path: Option<PathBuf>) -> egg_mode::Token {
+    let con_token = egg_mode::KeyPair::new(
+        opts.common.consumer_key.clone().unwrap(),
+        opts.common.consumer_secret.clone().unwrap(),
+    );
+    let cached_token = if opts.common.no_cache {
+        None
+    } else {
+        token_cache_path
+            .as_ref()
+            .and_then(|path| load_cached_token(path, &con_token.key))
+    };
+
+    if let (Some(access_token), Some(access_token_secret)) = (
+        opts.common.access_token.clone(),
+        opts.common.access_token_secret.clone(),
+    ) {
+        egg_mode::Token::Access {
+            consumer: con_token,
+            access: egg_mode::KeyPair::new(access_token, access_token_secret),
+        }
+    } else if let Some(access_token) = cached_token {
+        info!("🔑 Using cached access token");
+        egg_mode::Token::Access {
+            consumer: con_token,
+            access: access_token,
+        }
+    } else {
+        let request_token = match request_token(&con_token).await {
+            Ok(request_token) => request_token,
+            Err(e) => {
+                error!("🚨 Could not start authorization: {}", e);
+                std::process::exit(1);
+            }
+        };
+        let auth_url = egg_mode::auth::authorize_url(&request_token);
+        // Prompts go to stderr, leaving stdout for --json-report.
+        e_cyan_ln!(
+            "No access token provided, please authorize your account with this URL: {}",
+            auth_url
+        );
+        let mut editor = rustyline::Editor::<()>::with_config(
+            rustyline::Config::builder()
+                .output_stream(rustyline::OutputStreamType::Stderr)
+                .build(),
+        );
+        let auth_code = editor
+            .readline("Please enter the authorization PIN: ")
+            .unwrap();
+        let auth_code = auth_code.trim();
+        let token = match access_token(con_token, &request_token, auth_code).await {
+            Ok(t) => t,
+            Err(_) => {
+                error!("Invalid PIN");
+                std::process::exit(1);
+            }
+        };
+        if let (false, Some(path), egg_mode::Token::Access { consumer, access }) =
+            (opts.common.no_cache, &token_cache_path, &token)
+        {
+            let cached = CachedToken {
+                consumer_key: consumer.key.to_string(),
+                access_token: access.key.to_string(),
+                access_token_secret: access.secret.to_string(),
+            };
+            if let Err(e) = save_cached_token(path, &cached) {
+                warn!("⚠️ Failed to cache access token: {}", e);
+            }
+        }
+        token
+    }
+}
+
+/// Gets an OAuth 2.0 bearer token from the cache, refreshing it if it has expired, or by logging
+/// in through the browser.
+async fn oauth2_token(opts: &Opts) -> egg_mode::Token {
+    let client = oauth2::Client {
+        id: opts.common.client_id.as_deref().unwrap(),
+        secret: opts.common.client_secret.as_deref(),
+    };
+    let cache_path = oauth2_token_cache_path().filter(|_| !opts.common.no_cache);
+    let cached = cache_path
+        .as_ref()
+        .and_then(|path| oauth2::load_cached_token(path, client.id));
+    let token = match cached {
+        Some(cached) if !cached.is_expired() => {
+            info!("🔑 Using cached access token");
+            return egg_mode::Token::Bearer(cached.access_token);
+        }
+        Some(oauth2::CachedToken {
+            refresh_token: Some(refresh_token),
+            ..
+        }) => match oauth2::refresh(&client, &refresh_token).await {
+            Ok(token) => {
+                info!("🔑 Refreshed cached access token");
+                Ok(token)
+            }
+            Err(e) => {
+                warn!("⚠️ Failed to refresh cached access token: {}", e);
+                oauth2::login(&client, opts.common.redirect_port).await
+            }
+        },
+        _ => oauth2::login(&client, opts.common.redirect_port).await,
+    };
+    let token = match token {
+        Ok(token) => token,
+        Err(e) => {
+            error!("🚨 OAuth 2.0 login failed: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Some(path) = &cache_path {
+        if let Err(e) = oauth2::save_cached_token(path, &token) {
+            warn!("⚠️ Failed to cache access token: {}", e);
+        }
+    }
+    egg_mode::Token::Bearer(token.access_token)
+}
+
+/// Logs in with the PIN flow or OAuth 2.0, removing the cached tokens first with --logout, and
+/// looks up the account that's logged in. Exits if either fails.
+async fn log_in(opts: &Opts) -> (egg_mode::Token, User) {
+    let token_cache_path = token_cache_path();
+    if opts.common.logout {
+        for path in [token_cache_path.clone(), oauth2_token_cache_path()]
+            .iter()
+            .flatten()
+            .filter(|path| path.exists())
+        {
+            match std::fs::remove_file(path) {
+                Ok(_) => info!("👋 Removed cached access token"),
+                Err(e) => {
+                    error!("🚨 Failed to remove cached access token: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
+    let token = if opts.common.oauth2 {
+        oauth2_token(opts).await
+    } else {
+        pin_token(opts, token_cache_path).await
+    };
+
+    let user = if opts.common.oauth2 {
+        oauth2::me(&token)
+            .await
+            .and_then(|me| match me.id.parse::<u64>() {
+                Ok(id) => Ok(User {
+                    id,
+                    screen_name: me.username,
+                }),
+                Err(_) => Err(egg_mode::error::Error::InvalidResponse(
+                    "the account ID from /2/users/me isn't a number",
+                    Some(me.id),
+                )),
+            })
+    } else {
+        let request = egg_mode::raw::request_get(
+            "https://api.twitter.com/1.1/account/verify_credentials.json",
+            &token,
+            None,
+        );
+        net::send_json::<User>(request)
+            .await
+            .map(|response| response.response)
+    };
+    match user {
+        Ok(user) => {
+            info!("🔓 Logged in successfully");
+            (token, user)
+        }
+        Err(e) => {
+            match (&opts.common.proxy, &e) {
+                (
+                    Some(proxy),
+                    egg_mode::error::Error::NetError(_) | egg_mode::error::Error::IOError(_),
+                ) => error!(
+                    "🚨 Could not reach twitter through the proxy {}: {}",
+                    proxy, e
+                ),
+                _ => error!("🚨 {}", e),
+            }
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Runs the `damae` command line tool with the arguments of the process. Errors are logged, and
+/// exit the process with a non-zero status.
+pub async fn run_cli() {
+    let matches = Cli::into_app().get_matches();
+    // The config file only fills in options that weren't given on the command line, which is
+    // decided from the matches of the subcommand that was used.
+    let (mut opts, matches, deprecated) = match matches.subcommand() {
+        Some((_, sub_matches)) => match Command::from_arg_matches(&matches).unwrap() {
+            Command::Stats => {
+                print_stats();
+                return;
+            }
+            Command::HelpFilters => {
+                filters::print_help();
+                return;
+            }
+            command => (
+                command
+                    .into_opts()
+                    .expect("every other command deletes something"),
+                sub_matches,
+                false,
+            ),
+        },
+        None => (Opts::from_arg_matches(&matches).unwrap(), &matches, true),
+    };
+    init_logger(&opts);
+    if deprecated {
+        warn!(
+            "⚠️ Running damae without a subcommand is deprecated, use `damae tweets`, `damae likes`, or `damae dms` instead"
+        );
+    }
+    let config = load_config(&opts);
+    apply_config(&mut opts, config, matches);
+    net::init(opts.common.proxy.clone());
+    if opts.common.print_config {
+        print!("{}", toml::to_string(&effective_config(&opts)).unwrap());
+        return;
+    }
+    if opts.common.max_tasks == 0 {
+        error!(
+            "🚨 max_tasks in the config file must be at least 1, use a positive number of tasks"
+        );
+        std::process::exit(1);
+    }
+    if opts.common.interactive {
+        opts.common.max_tasks = 1;
+    }
+    if opts.mode == Mode::Timeline {
+        let unsupported = [
+            (opts.common.archive_path.is_some(), "an archive path"),
+            (opts.common.oauth2, "--oauth2"),
+            (opts.tweets.keep_first.is_some(), "--keep-first"),
+            (opts.tweets.list_clients, "--list-clients"),
+        ];
+        if let Some((_, what)) = unsupported.iter().find(|(given, _)| *given) {
+            error!(
+                "🚨 damae timeline reads your tweets from twitter, so it can't be used with {}",
+                what
+            );
+            std::process::exit(1);
+        }
+    } else if opts.common.archive_path.is_none() {
+        error!("🚨 The path to your twitter archive is missing");
+        std::process::exit(1);
+    }
+    let remote_archive = opts
+        .common
+        .archive_path
+        .clone()
+        .filter(|archive_path| remote::is_remote(archive_path));
+    if let Some(url) = remote_archive {
+        let show_progress = !opts.common.quiet && opts.common.progress() == Progress::Bar;
+        match remote::fetch(&url, show_progress).await {
+            Ok(path) => opts.common.archive_path = Some(path.to_string_lossy().into_owned()),
+            Err(e) => {
+                error!("🚨 Could not download the archive: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if opts.tweets.list_clients {
+        list_clients(Path::new(
+            opts.common.archive_path.as_deref().unwrap_or_default(),
+        ));
+        return;
+    }
+
+    if let (Some(older_than), Some(newer_than)) = (opts.tweets.older_than, opts.tweets.newer_than) {
+        if newer_than >= older_than {
+            error!("🚨 The --after date must be earlier than the --before date");
+            std::process::exit(1);
+        }
+    }
+    if let (Some(since_id), Some(max_id)) = (opts.tweets.since_id, opts.tweets.max_id) {
+        if since_id >= max_id {
+            error!("🚨 The --since-id must be less than the --max-id");
+            std::process::exit(1);
+        }
+    }
+    if opts.likes.delete_likes_older_than.is_some() && opts.mode != Mode::Likes {
+        error!("🚨 --delete-likes-older-than can only be used with --mode likes");
+        std::process::exit(1);
+    }
+    if opts.common.seed.is_some() && !opts.common.shuffle && opts.common.sample_fraction.is_none() {
+        error!("🚨 --seed needs --shuffle or --sample-fraction");
+        std::process::exit(1);
+    }
+    if !opts.tweets.redact.is_empty() && !opts.common.dry_run && !opts.tweets.enable_redact_repost {
+        error!(
+            "🚨 --redact deletes tweets and posts new ones in their place, try it with --dry-run \
+             first and pass --enable-redact-repost to go ahead"
+        );
+        std::process::exit(1);
+    }
+    let dates = DateRange::from_opts(&opts, Utc::now());
+    if dates.is_empty() {
+        error!("🚨 The date filters don't leave any time range to delete tweets from");
+        std::process::exit(1);
+    }
+
+    for (flag, env, credential) in [
+        (
+            "--consumer-key",
+            "DAMAE_CONSUMER_KEY",
+            &mut opts.common.consumer_key,
+        ),
+        (
+            "--consumer-secret",
+            "DAMAE_CONSUMER_SECRET",
+            &mut opts.common.consumer_secret,
+        ),
+        (
+            "--access-token",
+            "DAMAE_ACCESS_TOKEN",
+            &mut opts.common.access_token,
+        ),
+        (
+            "--access-token-secret",
+            "DAMAE_ACCESS_TOKEN_SECRET",
+            &mut opts.common.access_token_secret,
+        ),
+        ("--client-id", "DAMAE_CLIENT_ID", &mut opts.common.client_id),
+        (
+            "--client-secret",
+            "DAMAE_CLIENT_SECRET",
+            &mut opts.common.client_secret,
+        ),
+    ] {
+        if let Some(value) = credential {
+            match check_credential(value) {
+                Ok(trimmed) => *value = trimmed.to_string(),
+                Err(e) => {
+                    error!("🚨 The value of {} (or {}) {}", flag, env, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
+    if opts.common.oauth2 {
+        if opts.common.client_id.is_none() {
+            error!("🚨 No client ID provided, pass --client-id or set DAMAE_CLIENT_ID");
+            std::process::exit(1);
+        }
+        if opts.mode == Mode::Dms {
+            error!("🚨 Direct messages can't be deleted with --oauth2, log in with the PIN flow");
+            std::process::exit(1);
+        }
+        if opts.common.verify_after {
+            error!("🚨 --verify-after isn't supported with --oauth2");
+            std::process::exit(1);
+        }
+    } else if opts.common.consumer_key.is_none() {
+        error!("🚨 No consumer key provided, pass --consumer-key or set DAMAE_CONSUMER_KEY");
+        std::process::exit(1);
+    } else if opts.common.consumer_secret.is_none() {
+        error!(
+            "🚨 No consumer secret provided, pass --consumer-secret or set DAMAE_CONSUMER_SECRET"
+        );
+        std::process::exit(1);
+    }
+
+    let match_regex = opts.tweets.match_regex.as_ref().map(|pattern| {
+        match RegexBuilder::new(pattern)
+            .case_insensitive(opts.tweets.ignore_case)
+            .build()
+        {
+            Ok(regex) => regex,
+            Err(e) => {
+                error!("🚨 Invalid --match-regex pattern: {}", e);
+                std::process::exit(1);
+            }
+        }
+    });
+
+    let mut keep_ids = match &opts.common.keep_ids {
+        Some(path) => read_id_list(path).unwrap_or_else(|e| {
+            error!("🚨 Could not read {}: {}", path.display(), e);
+            std::process::exit(1);
+        }),
+        None => HashSet::new(),
+    };
+    keep_ids.extend(opts.common.keep_id.iter().map(|id| id.to_string()));
+
+    let mut only_ids = match &opts.common.only_ids {
+        Some(path) => read_id_list(path).unwrap_or_else(|e| {
+            error!("🚨 Could not read {}: {}", path.display(), e);
+            std::process::exit(1);
+        }),
+        None => HashSet::new(),
+    };
+    only_ids.extend(opts.common.id.iter().map(|id| id.to_string()));
+    let previous_log = opts.common.diff_against.as_ref().map(|path| {
+        read_log_ids(path).unwrap_or_else(|e| {
+            error!("🚨 Could not read log file {}: {}", path.display(), e);
+            std::process::exit(1);
+        })
+    });
+    let imported = opts.common.import_state.as_ref().map(|path| {
+        load_bundle(path).unwrap_or_else(|e| {
+            error!("🚨 Could not read {}: {}", path.display(), e);
+            std::process::exit(1);
+        })
+    });
+    let replayed = opts.common.replay.as_ref().map(|path| {
+        read_replay_ids(path, &opts.common.status).unwrap_or_else(|e| {
+            error!("🚨 Could not read log file {}: {}", path.display(), e);
+            std::process::exit(1);
+        })
+    });
+    if opts.common.ids_from_stdin {
+        if let Some(prompt) = stdin_prompt(&opts) {
+            error!(
+                "🚨 --ids-from-stdin can't be used with {}, since both read from stdin",
+                prompt
+            );
+            error!("Pass an access token (or log in once without --ids-from-stdin) and -y");
+            std::process::exit(1);
+        }
+        let mut contents = String::new();
+        if let Err(e) = std::io::stdin().read_to_string(&mut contents) {
+            error!("🚨 Could not read tweet IDs from stdin: {}", e);
+            std::process::exit(1);
+        }
+        match parse_id_list(&contents) {
+            Ok(ids) if ids.is_empty() => {
+                error!("🚨 No tweet IDs were given on stdin");
+                std::process::exit(1);
+            }
+            Ok(ids) => only_ids.extend(ids),
+            Err(e) => {
+                error!("🚨 Could not read tweet IDs from stdin: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // The timeline is read from twitter, so there's no reading it without logging in first.
+    let login = if opts.mode == Mode::Timeline {
+        Some(log_in(&opts).await)
+    } else {
+        None
+    };
+    let archive_path = Path::new(opts.common.archive_path.as_deref().unwrap_or_default());
+    // Reading a large archive can take a while, so show that something is happening.
+    let spinner = if opts.common.quiet || opts.common.progress() != Progress::Bar {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new_spinner()
+    };
+    spinner.set_style(ProgressStyle::default_spinner().template("{spinner} {msg} ({elapsed})"));
+    spinner.set_message(match &login {
+        Some(_) => "Reading your timeline".to_string(),
+        None => format!("Reading {}", archive_path.display()),
+    });
+    spinner.enable_steady_tick(100);
+    let account = match &login {
+        // Every tweet on the timeline is the logged in account's own.
+        Some((_, user)) => Some(Account {
+            username: user.screen_name.clone(),
+            account_id: user.id.to_string(),
+        }),
+        None => or_exit(load_account(archive_path)),
+    };
+    if opts.tweets.self_quotes_only && opts.tweets.invert && account.is_none() {
+        spinner.finish_and_clear();
+        error!("🚨 --self-quotes-only needs data/account.js to be combined with --invert");
+        std::process::exit(1);
+    }
+    let self_replies = opts.tweets.self_replies_only || opts.tweets.exclude_self_replies;
+    if self_replies && opts.tweets.invert && account.is_none() {
+        spinner.finish_and_clear();
+        error!(
+            "🚨 --self-replies-only and --exclude-self-replies need data/account.js to be \
+             combined with --invert"
+        );
+        std::process::exit(1);
+    }
+    let mut state = opts
+        .common
+        .state_file
+        .as_deref()
+        .map(load_state)
+        .unwrap_or_default();
+    let mut threads = ThreadIndex::default();
+    // Every tweet in the archive, when the ones that aren't deleted are written out at the end.
+    let mut all_tweets = Vec::new();
+    // The oldest tweets in the archive seen so far, for --keep-first.
+    let mut oldest = BTreeSet::new();
+    let mut targets = match opts.mode {
+        Mode::Tweets | Mode::Timeline => {
+            let mut select = |t: &WrappedTweet| {
+                if let (Some(n), Some(created_at)) = (opts.tweets.keep_first, t.tweet.created_at())
+                {
+                    oldest.insert((created_at, t.tweet.id.clone()));
+                    if oldest.len() > n {
+                        oldest.pop_last();
+                    }
+                }
+                if opts.tweets.output_remaining.is_some() {
+                    all_tweets.push((t.tweet.id.clone(), t.raw.clone()));
+                }
+                if opts.tweets.keep_self_threads
+                    || opts.tweets.dedupe_threads
+                    || opts.tweets.export_thread_markdown.is_some()
+                    || opts.tweets.report_orphans
+                    || opts.tweets.keep_orphans
+                    || opts.tweets.orphans_only
+                {
+                    threads.record(&t.tweet);
+                }
+                keep_tweet(
+                    t,
+                    &opts,
+                    dates,
+                    match_regex.as_ref(),
+                    account.as_ref().map(|account| account.username.as_str()),
+                    account.as_ref().map(|account| account.account_id.as_str()),
+                ) != opts.tweets.invert
+            };
+            let tweets = match &login {
+                Some((token, user)) => {
+                    or_exit(fetch_timeline(user.id, token, &opts, &spinner).await)
+                        .into_iter()
+                        .filter(|t| select(t))
+                        .collect()
+                }
+                None => or_exit(load_tweets(archive_path, select)),
+            };
+            tweets
+                .into_iter()
+                .map(|t| {
+                    let repost = Repost::redact(&t.tweet, &opts.tweets.redact);
+                    Target {
+                        repost,
+                        ..Target::from(t)
+                    }
+                })
+                .collect::<Vec<_>>()
+        }
+        Mode::Likes => {
+            let cutoff = opts.likes.delete_likes_older_than.map(start_of_day);
+            or_exit(load_likes(archive_path))
+                .into_iter()
+                .map(Target::from)
+                .filter(|t| cutoff.is_none_or(|cutoff| t.created_at.is_some_and(|c| c < cutoff)))
+                .collect()
+        }
+        Mode::Dms => or_exit(load_direct_messages(archive_path))
+            .into_iter()
+            .map(Target::from)
+            .collect(),
+        Mode::Unblock => or_exit(load_related_accounts(archive_path, "block", "blocking"))
+            .into_iter()
+            .map(Target::from)
+            .collect(),
+        Mode::Unmute => or_exit(load_related_accounts(archive_path, "mute", "muting"))
+            .into_iter()
+            .map(Target::from)
+            .collect(),
+    };
+    spinner.finish_and_clear();
+    if !opts.tweets.redact.is_empty() {
+        let before = targets.len();
+        targets.retain(|t| t.repost.is_some());
+        info!(
+            "✂️ Keeping {} selected tweets that --redact doesn't change",
+            before - targets.len()
+        );
+    }
+    let (valid, invalid): (Vec<_>, Vec<_>) = targets.into_iter().partition(Target::has_numeric_ids);
+    targets = valid;
+    if !invalid.is_empty() {
+        warn!(
+            "⚠️ Skipping {} {} with an ID that isn't a number: {}",
+            invalid.len(),
+            opts.mode.noun(),
+            list_ids(invalid.iter().map(|t| t.id.as_str()))
+        );
+    }
+    if opts.tweets.keep_self_threads {
+        let self_threads = threads.self_thread_ids();
+        let before = targets.len();
+        targets.retain(|t| !self_threads.contains(t.id.as_str()));
+        info!(
+            "🧵 Keeping {} tweets that are part of self-threads",
+            before - targets.len()
+        );
+    }
+    if opts.tweets.dedupe_threads {
+        let last_tweets = threads.self_thread_leaves();
+        let before = targets.len();
+        targets.retain(|t| !last_tweets.contains(t.id.as_str()));
+        info!(
+            "🧵 Keeping {} tweets that end self-threads",
+            before - targets.len()
+        );
+    }
+    let orphans = threads.orphan_ids();
+    if opts.tweets.report_orphans {
+        info!(
+            "👻 {} of the {} selected tweets are replies to tweets that aren't in the archive",
+            targets
+                .iter()
+                .filter(|t| orphans.contains(t.id.as_str()))
+                .count(),
+            targets.len()
+        );
+    }
+    if opts.tweets.keep_orphans {
+        let before = targets.len();
+        targets.retain(|t| !orphans.contains(t.id.as_str()));
+        info!(
+            "👻 Keeping {} replies to tweets that aren't in the archive",
+            before - targets.len()
+        );
+    }
+    if opts.tweets.orphans_only {
+        let before = targets.len();
+        targets.retain(|t| orphans.contains(t.id.as_str()));
+        info!(
+            "👻 Keeping {} tweets that aren't replies to tweets outside the archive",
+            before - targets.len()
+        );
+    }
+    let mut kept_media = 0;
+    if opts.tweets.preserve_media_tweets {
+        // A tweet has a media URL for every photo, video, or GIF attached to it.
+        let before = targets.len();
+        targets.retain(|t| t.media_urls.is_empty());
+        kept_media = before - targets.len();
+        info!(
+            "🖼️ Keeping {} tweets with media because of --preserve-media-tweets",
+            kept_media
+        );
+    }
+    let (token, user) = match login {
+        Some(login) => login,
+        None => log_in(&opts).await,
+    };
+
+    match &account {
+        Some(account) if account.account_id != user.id.to_string() => {
+            if opts.common.force_account_mismatch {
+                warn!(
+                    "⚠️ This archive belongs to @{}, but you're logged in as @{}",
+                    account.username, user.screen_name
+                );
+            } else {
+                error!(
+                    "🚨 This archive belongs to @{}, but you're logged in as @{}",
+                    account.username, user.screen_name
+                );
+                error!("Pass --force-account-mismatch if you really want to continue");
+                std::process::exit(1);
+            }
+        }
+        Some(_) => {}
+        None => {
+            warn!(
+                "⚠️ Could not find data/account.js, unable to confirm the archive belongs to @{}",
+                user.screen_name
+            );
+        }
+    }
+
+    if self_replies && account.is_none() {
+        // Without data/account.js the tweets couldn't be checked while loading the archive.
+        let user_id = user.id.to_string();
+        let before = targets.len();
+        targets.retain(|t| {
+            filters::keeps_self_replies(&opts.tweets, t.in_reply_to_user_id.as_deref(), &user_id)
+        });
+        info!(
+            "↩️ Keeping {} tweets because of --self-replies-only or --exclude-self-replies",
+            before - targets.len()
+        );
+    }
+    if opts.tweets.self_quotes_only && account.is_none() {
+        // Without data/account.js the quoted tweets couldn't be checked while loading either.
+        let before = targets.len();
+        targets.retain(|t| {
+            t.quoted_username
+                .as_ref()
+                .is_some_and(|quoted| quoted.eq_ignore_ascii_case(&user.screen_name))
+        });
+        info!(
+            "💬 Keeping {} tweets that don't quote your own because of --self-quotes-only",
+            before - targets.len()
+        );
+    }
+
+    if opts.mode == Mode::Dms {
+        // Only messages sent by the account can be deleted
+        let user_id = user.id.to_string();
+        let total = targets.len();
+        targets.retain(|t| t.sender_id.as_deref() == Some(user_id.as_str()));
+        info!(
+            "✉️ Found {} messages sent by @{}, {} received messages will be kept",
+            targets.len(),
+            user.screen_name,
+            total - targets.len()
+        );
+    }
+
+    if !only_ids.is_empty() {
+        targets.retain(|t| only_ids.contains(&t.id));
+        let found = targets
+            .iter()
+            .map(|t| t.id.as_str())
+            .collect::<HashSet<_>>();
+        let mut missing = only_ids
+            .iter()
+            .filter(|id| !found.contains(id.as_str()))
+            .collect::<Vec<_>>();
+        missing.sort();
+        for id in &missing {
+            warn!(
+                "⚠️ {} is not in the archive, or was excluded by another filter",
+                id
+            );
+        }
+        info!(
+            "🎯 Restricting to {} of the {} listed {}",
+            targets.len(),
+            only_ids.len(),
+            opts.mode.noun()
+        );
+    }
+
+    if let (Some(path), Some(replayed)) = (&opts.common.replay, &replayed) {
+        targets.retain(|t| replayed.contains(&t.id));
+        info!(
+            "🔁 Replaying {} of the {} {} with a matching status in {}",
+            targets.len(),
+            replayed.len(),
+            opts.mode.noun(),
+            path.display()
+        );
+    }
+
+    if !keep_ids.is_empty() {
+        let before = targets.len();
+        targets.retain(|t| !keep_ids.contains(&t.id));
+        info!(
+            "🛡️ Keeping {} {} from the keep list",
+            before - targets.len(),
+            opts.mode.noun()
+        );
+    }
+
+    let mut kept_first = 0;
+    if let Some(n) = opts.tweets.keep_first {
+        let oldest = oldest.iter().map(|(_, id)| id).collect::<HashSet<_>>();
+        let before = targets.len();
+        targets.retain(|t| !oldest.contains(&t.id));
+        kept_first = before - targets.len();
+        info!(
+            "🕰️ Keeping {} of your {} oldest tweets because of --keep-first",
+            kept_first, n
+        );
+    }
+
+    if matches!(opts.mode, Mode::Tweets | Mode::Timeline)
+        && !opts.tweets.delete_pinned
+        && !targets.is_empty()
+    {
+        match pinned_tweet_id(user.id, &token).await {
+            Ok(Some(pinned)) => {
+                let before = targets.len();
+                targets.retain(|t| t.id != pinned);
+                if targets.len() < before {
+                    info!(
+                        "📌 Keeping your pinned tweet {}, pass --delete-pinned to delete it",
+                        pinned
+                    );
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warn!(
+                "⚠️ Could not look up your pinned tweet, it won't be protected: {}",
+                e
+            ),
+        }
+    }
+
+    // The bundle is checked against the archive's account, or the one that's logged in if the
+    // archive doesn't say.
+    let account_id = account
+        .as_ref()
+        .map_or_else(|| user.id.to_string(), |account| account.account_id.clone());
+    let mut bundle = match (imported, &opts.common.import_state) {
+        (Some(bundle), Some(path)) => {
+            if bundle.account_id != account_id {
+                error!(
+                    "🚨 {} is from the account with ID {}, not this archive's account ({})",
+                    path.display(),
+                    bundle.account_id,
+                    account_id
+                );
+                std::process::exit(1);
+            }
+            info!(
+                "📦 Carrying on from {} after {} runs: {} {} {}, {} failed",
+                path.display(),
+                bundle.runs,
+                bundle.deleted,
+                opts.mode.noun(),
+                opts.mode.past_tense().to_lowercase(),
+                bundle.failed_ids.len()
+            );
+            state.processed.extend(bundle.processed.iter().cloned());
+            bundle
+        }
+        _ => StateBundle {
+            account_id,
+            ..StateBundle::default()
+        },
+    };
+    bundle.processed.extend(state.processed.iter().cloned());
+
+    if !state.processed.is_empty() {
+        let before = targets.len();
+        targets.retain(|t| !state.processed.contains(&t.id));
+        info!(
+            "⏭️ Skipping {} {} already processed in a previous run",
+            before - targets.len(),
+            opts.mode.noun()
+        );
+    }
+
+    match opts.common.order {
+        Some(Order::Oldest) => targets.sort_by_key(|t| t.created_at),
+        Some(Order::Newest) => targets.sort_by_key(|t| std::cmp::Reverse(t.created_at)),
+        None => {}
+    }
+    let seed = opts.common.seed.unwrap_or_else(rand::random);
+    if let Some(fraction) = opts.common.sample_fraction {
+        let before = targets.len();
+        targets = sample(
+            targets,
+            fraction,
+            opts.common
+                .sample_strategy
+                .unwrap_or(SampleStrategy::Uniform),
+            opts.tweets.timezone,
+            &mut StdRng::seed_from_u64(seed),
+        );
+        info!(
+            "🎲 Picked {} of the {} {} with --sample-fraction",
+            targets.len(),
+            before,
+            opts.mode.noun()
+        );
+        debug!("Sampled with --seed {}", seed);
+    }
+    if opts.common.shuffle {
+        targets.shuffle(&mut StdRng::seed_from_u64(seed));
+        debug!("Shuffled with --seed {}", seed);
+    }
+
+    if let Some(limit) = opts.common.limit {
+        targets.truncate(limit);
+    }
+
+    if let (Some(path), Some(previous)) = (&opts.common.diff_against, &previous_log) {
+        print_diff(&targets, previous, path, opts.common.verbose > 0);
+    }
+
+    if opts.common.summary_only {
+        info!(
+            "📅 {} {} would be {}, by year:",
+            targets.len(),
+            opts.mode.noun(),
+            opts.mode.past_tense().to_lowercase()
+        );
+        print_year_histogram(&count_by_year(
+            targets.iter().filter_map(|t| t.created_at.as_ref()),
+            opts.tweets.timezone,
+        ));
+        return;
+    }
+
+    if !opts.common.dry_run && !opts.common.yes && !opts.common.quiet {
+        print_sample(&targets, opts.mode, opts.common.preview_text_width);
+    }
+    if opts.common.dry_run {
+        warn!("🥸 Running in dry-run mode");
+    } else if !opts.common.yes && !confirm_run(&opts, targets.len(), &user.screen_name) {
+        error!("Aborting");
+        std::process::exit(1);
+    }
+
+    let source = if opts.mode == Mode::Timeline {
+        "your timeline"
+    } else {
+        "archive"
+    };
+    info!(
+        "🔎 Loaded {} {} from {}",
+        targets.len(),
+        opts.mode.noun(),
+        source
+    );
+    info!("✨ Starting to {} {}", opts.mode.verb(), opts.mode.noun());
+
+    if let Some(path) = &opts.common.backup {
+        match write_backup(path, &targets) {
+            Ok(_) => {
+                info!(
+                    "💾 Backed up {} {} to {}",
+                    targets.len(),
+                    opts.mode.noun(),
+                    path.display()
+                );
+            }
+            Err(e) => {
+                error!("🚨 Could not write backup to {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(path) = &opts.tweets.output_remaining {
+        match write_remaining(path, &all_tweets, &targets, &state.processed) {
+            Ok(count) => info!(
+                "💾 Wrote the {} tweets that are kept to {}",
+                count,
+                path.display()
+            ),
+            Err(e) => {
+                error!("🚨 Could not write to {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(dir) = &opts.tweets.export_thread_markdown {
+        match export_markdown(dir, &targets, &threads, &user.screen_name).await {
+            Ok(files) => info!("📝 Exported {} Markdown files to {}", files, dir.display()),
+            Err(e) => {
+                error!("🚨 Could not export to {}: {}", dir.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    tokio::spawn(handle_ctrl_c(stop.clone()));
+    let summary = run(&targets, &token, user.id, &opts, state, stop).await;
+    if opts.common.dry_run {
+        info!(
+            "✅ Done! {} {} would have been {}",
+            summary.deleted,
+            opts.mode.noun(),
+            opts.mode.past_tense().to_lowercase()
+        );
+    } else {
+        info!(
+            "✅ Done! {} {} {}",
+            opts.mode.past_tense(),
+            summary.deleted - summary.already_deleted,
+            opts.mode.noun()
+        );
+        if summary.already_deleted > 0 {
+            info!(
+                "👻 {} {} were already gone, the archive may be out of date",
+                summary.already_deleted,
+                opts.mode.noun()
+            );
+        }
+    }
+    if summary.failed > 0 {
+        warn!(
+            "🚨 {} {} failed to {}",
+            summary.failed,
+            opts.mode.noun(),
+            opts.mode.verb()
+        );
+        if !summary.error_codes.is_empty() {
+            warn!(
+                "🚨 Errors by code: {}",
+                format_error_codes(&summary.error_codes)
+            );
+        }
+        if let Some(path) = &opts.common.save_failed_ids {
+            info!(
+                "🔁 Saved the failed IDs to {}, pass it to --only-ids to retry them",
+                path.display()
+            );
+        }
+    }
+    if kept_first > 0 {
+        info!(
+            "🕰️ Kept {} of your oldest tweets because of --keep-first",
+            kept_first
+        );
+    }
+    if kept_media > 0 {
+        info!(
+            "🖼️ Kept {} tweets with media because of --preserve-media-tweets",
+            kept_media
+        );
+    }
+    if summary.skipped > 0 {
+        info!("⏭️ Skipped {} {}", summary.skipped, opts.mode.noun());
+    }
+    if summary.remaining > 0 {
+        info!(
+            "🛑 Stopped early, {} {} were not processed",
+            summary.remaining,
+            opts.mode.noun()
+        );
+    }
+    if !summary.by_year.is_empty() {
+        info!("📅 Breakdown by year:");
+        print_year_histogram(&summary.by_year);
+    }
+    info!("⏱️ Finished in {}", HumanDuration(summary.elapsed));
+    if let Some(path) = &opts.common.export_state {
+        // A dry run has nothing to add, but the imported state is still carried over.
+        if !opts.common.dry_run {
+            bundle.record(&summary);
+        }
+        match save_bundle(path, &bundle) {
+            Ok(()) => info!(
+                "📦 Saved the state to {}, pass it to --import-state to carry on",
+                path.display()
+            ),
+            Err(e) => error!("🚨 Failed to write the state to {}: {}", path.display(), e),
+        }
+    }
+    if !opts.common.dry_run {
+        if let Some(path) = stats_path() {
+            if let Err(e) = record_stats(&path, &summary) {
+                warn!("⚠️ Failed to update stats file {}: {}", path.display(), e);
+            }
+        }
+    }
+    if opts.common.verify_after && !opts.common.dry_run && !summary.removed_ids.is_empty() {
+        if opts.mode == Mode::Dms {
+            warn!("⚠️ --verify-after can't check direct messages, skipping verification");
+        } else if !opts.mode.is_permanent() {
+            warn!("⚠️ --verify-after can't check blocks or mutes, skipping verification");
+        } else {
+            verify(&summary.removed_ids, &token, &opts).await;
+        }
+    }
+    let report = Report::new(targets.len(), &summary, &opts);
+    if opts.common.json_report {
+        println!("{}", serde_json::to_string(&report).unwrap());
+    }
+    if let Some(path) = &opts.common.report_html {
+        let html = html_report(&report, &summary, &opts, &user.screen_name);
+        match std::fs::write(path, html) {
+            Ok(()) => info!("📄 Wrote the report to {}", path.display()),
+            Err(e) => error!("🚨 Failed to write the report to {}: {}", path.display(), e),
+        }
+    }
+    if summary.failed > 0 {
+        std::process::exit(EXIT_FAILURES);
+    }
+}
@@ -0,0 +1,116 @@
+//! A small HTTP endpoint serving the progress of a run in the Prometheus text format, for
+//! keeping an eye on long unattended runs.
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// The counters of a run that are exported, shared with the tasks that update them.
+pub struct Metrics {
+    pub total: usize,
+    pub deleted: Arc<AtomicUsize>,
+    pub failed: Arc<AtomicUsize>,
+    pub rate_limit_waits: Arc<AtomicUsize>,
+}
+
+impl Metrics {
+    /// Renders the counters in the Prometheus text exposition format.
+    fn render(&self) -> String {
+        let metrics = [
+            (
+                "tweets_total",
+                "gauge",
+                "Number of items this run is going through",
+                self.total,
+            ),
+            (
+                "deleted_total",
+                "counter",
+                "Number of items removed so far, including ones that were already gone",
+                self.deleted.load(Ordering::SeqCst),
+            ),
+            (
+                "failed_total",
+                "counter",
+                "Number of items that couldn't be removed",
+                self.failed.load(Ordering::SeqCst),
+            ),
+            (
+                "rate_limit_waits",
+                "counter",
+                "Number of times the run paused until the rate limit reset",
+                self.rate_limit_waits.load(Ordering::SeqCst),
+            ),
+        ];
+        metrics
+            .iter()
+            .map(|(name, kind, help, value)| {
+                format!(
+                    "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}\n",
+                    name = name,
+                    help = help,
+                    kind = kind,
+                    value = value
+                )
+            })
+            .collect()
+    }
+}
+
+/// The running metrics server, which stops when [`MetricsServer::shutdown`] is called.
+pub struct MetricsServer {
+    shutdown: oneshot::Sender<()>,
+    server: JoinHandle<Result<(), hyper::Error>>,
+}
+
+impl MetricsServer {
+    /// Stops accepting requests and waits for the ones in progress to finish.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(());
+        let _ = self.server.await;
+    }
+}
+
+/// Starts serving the metrics on `addr`. They're the only thing served, so any path works,
+/// including the usual `/metrics`.
+pub fn serve(addr: SocketAddr, metrics: Metrics) -> Result<MetricsServer, String> {
+    let metrics = Arc::new(metrics);
+    let make_service = make_service_fn(move |_| {
+        let metrics = metrics.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                let metrics = metrics.clone();
+                async move {
+                    if request.method() != Method::GET {
+                        return Ok::<_, Infallible>(
+                            Response::builder()
+                                .status(StatusCode::METHOD_NOT_ALLOWED)
+                                .body(Body::empty())
+                                .unwrap(),
+                        );
+                    }
+                    Ok(Response::builder()
+                        .header("Content-Type", "text/plain; version=0.0.4")
+                        .body(Body::from(metrics.render()))
+                        .unwrap())
+                }
+            }))
+        }
+    });
+    let (shutdown, shutdown_signal) = oneshot::channel::<()>();
+    let server = Server::try_bind(&addr)
+        .map_err(|e| format!("could not listen on {}: {}", addr, e))?
+        .serve(make_service)
+        .with_graceful_shutdown(async {
+            shutdown_signal.await.ok();
+        });
+    Ok(MetricsServer {
+        shutdown,
+        server: tokio::spawn(server),
+    })
+}
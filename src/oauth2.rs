@@ -0,0 +1,369 @@
+//! OAuth 2.0 login using the Authorization Code flow with PKCE, as an alternative to the OAuth
+//! 1.0a PIN flow. The resulting bearer token only works with twitter's v2 API.
+
+use chrono::{DateTime, Utc};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use log::info;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::{oneshot, Mutex};
+
+const AUTHORIZE_URL: &str = "https://twitter.com/i/oauth2/authorize";
+const TOKEN_URL: &str = "https://api.twitter.com/2/oauth2/token";
+/// Everything needed to delete tweets, remove likes and retweets, and refresh the token later.
+const SCOPES: &str =
+    "tweet.read tweet.write users.read like.read like.write block.write mute.write offline.access";
+
+/// Access token saved to disk after a successful OAuth 2.0 login, so later runs can skip it.
+#[derive(Serialize, Deserialize)]
+pub struct CachedToken {
+    pub client_id: String,
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl CachedToken {
+    /// Returns whether the access token has expired, or is about to.
+    pub fn is_expired(&self) -> bool {
+        self.expires_at - chrono::Duration::minutes(1) <= Utc::now()
+    }
+}
+
+/// The client registered in the twitter developer portal.
+pub struct Client<'a> {
+    pub id: &'a str,
+    /// Only confidential clients have a secret.
+    pub secret: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_in: i64,
+}
+
+#[derive(Deserialize, Default)]
+struct MeResponse {
+    data: Me,
+}
+
+/// The account an OAuth 2.0 token belongs to.
+#[derive(Deserialize, Default)]
+pub struct Me {
+    pub id: String,
+    pub username: String,
+}
+
+/// Loads the cached token, ignoring it if it was issued for a different client.
+pub fn load_cached_token(path: &Path, client_id: &str) -> Option<CachedToken> {
+    let cached: CachedToken = serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+    Some(cached).filter(|cached| cached.client_id == client_id)
+}
+
+/// Saves the token to the cache, readable only by the current user.
+pub fn save_cached_token(path: &Path, token: &CachedToken) -> std::io::Result<()> {
+    crate::write_private_file(path, &serde_json::to_vec(token)?)
+}
+
+/// Derives the PKCE code challenge for a code verifier.
+fn code_challenge(verifier: &str) -> String {
+    base64::encode_config(Sha256::digest(verifier.as_bytes()), base64::URL_SAFE_NO_PAD)
+}
+
+fn random_string(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+/// Opens a URL in the default browser, if there's a way to on this platform.
+fn open_browser(url: &str) {
+    let result = if cfg!(target_os = "macos") {
+        std::process::Command::new("open").arg(url).status()
+    } else if cfg!(windows) {
+        std::process::Command::new("cmd")
+            .args(["/C", "start", "", url])
+            .status()
+    } else {
+        std::process::Command::new("xdg-open").arg(url).status()
+    };
+    // The URL has been printed too, so there's nothing to do if this fails.
+    let _ = result;
+}
+
+/// Starts a local server that runs until twitter redirects the browser back to it. The server is
+/// listening by the time this returns, and the future returns the authorization code from the
+/// redirect.
+fn wait_for_code(
+    port: u16,
+    state: String,
+) -> Result<impl Future<Output = Result<String, String>>, String> {
+    let (sender, receiver) = oneshot::channel::<Result<String, String>>();
+    let sender = Arc::new(Mutex::new(Some(sender)));
+    let state = Arc::new(state);
+    let make_service = make_service_fn(move |_| {
+        let sender = sender.clone();
+        let state = state.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                let sender = sender.clone();
+                let state = state.clone();
+                async move {
+                    if request.method() != Method::GET || request.uri().path() != "/callback" {
+                        return Ok::<_, Infallible>(
+                            Response::builder()
+                                .status(StatusCode::NOT_FOUND)
+                                .body(Body::empty())
+                                .unwrap(),
+                        );
+                    }
+                    let params =
+                        url::form_urlencoded::parse(request.uri().query().unwrap_or("").as_bytes())
+                            .into_owned()
+                            .collect::<std::collections::HashMap<_, _>>();
+                    let result = match (params.get("code"), params.get("state")) {
+                        (_, Some(returned)) if **returned != *state => {
+                            Err("the authorization response didn't match the request".to_string())
+                        }
+                        (Some(code), Some(_)) => Ok(code.clone()),
+                        _ => Err(params
+                            .get("error")
+                            .cloned()
+                            .unwrap_or_else(|| "no authorization code was returned".to_string())),
+                    };
+                    let page = match &result {
+                        Ok(_) => "Damae is authorized, you can close this tab.".to_string(),
+                        Err(e) => format!("Authorization failed: {}", e),
+                    };
+                    if let Some(sender) = sender.lock().await.take() {
+                        let _ = sender.send(result);
+                    }
+                    Ok(Response::new(Body::from(page)))
+                }
+            }))
+        }
+    });
+    let (shutdown, shutdown_signal) = oneshot::channel::<()>();
+    let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], port)))
+        .map_err(|e| format!("could not listen on port {}: {}", port, e))?
+        .serve(make_service)
+        .with_graceful_shutdown(async {
+            shutdown_signal.await.ok();
+        });
+    let server = tokio::spawn(server);
+    Ok(async move {
+        let result = receiver
+            .await
+            .unwrap_or_else(|_| Err("the local server stopped unexpectedly".to_string()));
+        let _ = shutdown.send(());
+        let _ = server.await;
+        result
+    })
+}
+
+/// Makes a request to the token endpoint.
+async fn request_token(
+    client: &Client<'_>,
+    params: &[(&str, &str)],
+) -> Result<CachedToken, String> {
+    let body = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(params)
+        .append_pair("client_id", client.id)
+        .finish();
+    let mut request =
+        Request::post(TOKEN_URL).header("Content-Type", "application/x-www-form-urlencoded");
+    if let Some(secret) = client.secret {
+        let credentials = base64::encode(format!("{}:{}", client.id, secret));
+        request = request.header("Authorization", format!("Basic {}", credentials));
+    }
+    let request = request.body(Body::from(body)).unwrap();
+    let response = crate::net::client()
+        .request(request)
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = response.status();
+    let bytes = hyper::body::to_bytes(response.into_body())
+        .await
+        .map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        return Err(format!("{}: {}", status, String::from_utf8_lossy(&bytes)));
+    }
+    let token: TokenResponse = serde_json::from_slice(&bytes).map_err(|e| e.to_string())?;
+    Ok(CachedToken {
+        client_id: client.id.to_string(),
+        access_token: token.access_token,
+        refresh_token: token.refresh_token,
+        expires_at: Utc::now() + chrono::Duration::seconds(token.expires_in),
+    })
+}
+
+/// Sends the user to twitter to authorize the client, catching the redirect on a local server
+/// at `http://127.0.0.1:<port>/callback`, which must be registered as a callback URL for the
+/// client.
+pub async fn login(client: &Client<'_>, port: u16) -> Result<CachedToken, String> {
+    let verifier = random_string(64);
+    let state = random_string(32);
+    let redirect_uri = format!("http://127.0.0.1:{}/callback", port);
+    let challenge = code_challenge(&verifier);
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .append_pair("response_type", "code")
+        .append_pair("client_id", client.id)
+        .append_pair("redirect_uri", &redirect_uri)
+        .append_pair("scope", SCOPES)
+        .append_pair("state", &state)
+        .append_pair("code_challenge", &challenge)
+        .append_pair("code_challenge_method", "S256")
+        .finish();
+    let url = format!("{}?{}", AUTHORIZE_URL, query);
+    // Listen before opening the browser, which can redirect back right away if the account
+    // already authorized damae.
+    let code = wait_for_code(port, state)?;
+    info!(
+        "🌐 Please authorize your account in your browser, opening {}",
+        url
+    );
+    open_browser(&url);
+    let code = code.await?;
+    request_token(
+        client,
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("redirect_uri", &redirect_uri),
+            ("code_verifier", &verifier),
+        ],
+    )
+    .await
+}
+
+/// Exchanges a refresh token for a new access token.
+pub async fn refresh(client: &Client<'_>, refresh_token: &str) -> Result<CachedToken, String> {
+    request_token(
+        client,
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+        ],
+    )
+    .await
+}
+
+/// Looks up the account a bearer token belongs to.
+pub async fn me(token: &egg_mode::Token) -> Result<Me, egg_mode::error::Error> {
+    let request = egg_mode::raw::request_get("https://api.twitter.com/2/users/me", token, None);
+    let response: egg_mode::Response<MeResponse> = crate::net::send_json(request).await?;
+    Ok(response.response.data)
+}
+
+/// Deletes a tweet with the v2 API.
+pub async fn delete_tweet(
+    id: u64,
+    token: &egg_mode::Token,
+) -> Result<egg_mode::RateLimit, egg_mode::error::Error> {
+    delete(&format!("https://api.twitter.com/2/tweets/{}", id), token).await
+}
+
+/// Undoes a retweet of the given tweet with the v2 API.
+pub async fn unretweet(
+    user_id: u64,
+    original_id: u64,
+    token: &egg_mode::Token,
+) -> Result<egg_mode::RateLimit, egg_mode::error::Error> {
+    delete(
+        &format!(
+            "https://api.twitter.com/2/users/{}/retweets/{}",
+            user_id, original_id
+        ),
+        token,
+    )
+    .await
+}
+
+/// Removes a like with the v2 API.
+pub async fn unlike(
+    user_id: u64,
+    id: u64,
+    token: &egg_mode::Token,
+) -> Result<egg_mode::RateLimit, egg_mode::error::Error> {
+    delete(
+        &format!("https://api.twitter.com/2/users/{}/likes/{}", user_id, id),
+        token,
+    )
+    .await
+}
+
+/// Unblocks an account with the v2 API.
+pub async fn unblock(
+    user_id: u64,
+    target_id: u64,
+    token: &egg_mode::Token,
+) -> Result<egg_mode::RateLimit, egg_mode::error::Error> {
+    delete(
+        &format!(
+            "https://api.twitter.com/2/users/{}/blocking/{}",
+            user_id, target_id
+        ),
+        token,
+    )
+    .await
+}
+
+/// Unmutes an account with the v2 API.
+pub async fn unmute(
+    user_id: u64,
+    target_id: u64,
+    token: &egg_mode::Token,
+) -> Result<egg_mode::RateLimit, egg_mode::error::Error> {
+    delete(
+        &format!(
+            "https://api.twitter.com/2/users/{}/muting/{}",
+            user_id, target_id
+        ),
+        token,
+    )
+    .await
+}
+
+#[derive(Deserialize)]
+struct PostedTweet {
+    data: PostedTweetData,
+}
+
+#[derive(Deserialize)]
+struct PostedTweetData {
+    id: String,
+}
+
+/// Posts a tweet with the v2 API, as a reply if `in_reply_to` is set, and returns its ID.
+pub async fn post_tweet(
+    text: &str,
+    in_reply_to: Option<&str>,
+    token: &egg_mode::Token,
+) -> Result<String, egg_mode::error::Error> {
+    let mut body = serde_json::json!({ "text": text });
+    if let Some(id) = in_reply_to {
+        body["reply"] = serde_json::json!({ "in_reply_to_tweet_id": id });
+    }
+    let request = egg_mode::raw::request_post_json("https://api.twitter.com/2/tweets", token, body);
+    let response: egg_mode::Response<PostedTweet> = crate::net::send_json(request).await?;
+    Ok(response.response.data.id)
+}
+
+async fn delete(
+    url: &str,
+    token: &egg_mode::Token,
+) -> Result<egg_mode::RateLimit, egg_mode::error::Error> {
+    crate::net::send_empty(egg_mode::raw::request_delete(url, token, None)).await
+}
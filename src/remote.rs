@@ -0,0 +1,412 @@
+//! Archives given as an `http(s)://` or `s3://` URL instead of a local path. They're downloaded
+//! into the cache directory, and only downloaded again when the file on the server has changed.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac, NewMac};
+use hyper::body::HttpBody;
+use hyper::header::{HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use hyper::{Body, Request, Response, StatusCode, Uri};
+use indicatif::{ProgressBar, ProgressStyle};
+use log::{info, warn};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+/// How many redirects are followed before giving up on an `http(s)://` URL.
+const MAX_REDIRECTS: usize = 10;
+
+/// Returns whether the archive path is a URL to download rather than a path on disk.
+pub fn is_remote(archive_path: &str) -> bool {
+    ["http://", "https://", "s3://"]
+        .iter()
+        .any(|scheme| archive_path.starts_with(scheme))
+}
+
+/// What's remembered about a cached download, to ask the server whether the file has changed.
+#[derive(Serialize, Deserialize, Default)]
+struct CacheEntry {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// Returns the URL without its query string, which can hold credentials like the signature of a
+/// presigned URL, for use in messages.
+fn display_url(url: &str) -> &str {
+    url.split_once('?').map_or(url, |(url, _)| url)
+}
+
+/// Downloads the archive at `url` into the cache, unless the copy already there is still current,
+/// and returns the path to the downloaded zip file.
+pub async fn fetch(url: &str, show_progress: bool) -> Result<PathBuf, String> {
+    let dir = dirs::cache_dir()
+        .ok_or("could not find a cache directory to download it to")?
+        .join("damae")
+        .join("archives");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let name = hex(&Sha256::digest(url.as_bytes()));
+    let path = dir.join(format!("{}.zip", name));
+    let entry_path = dir.join(format!("{}.json", name));
+    let cached = if path.exists() {
+        std::fs::read_to_string(&entry_path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<CacheEntry>(&contents).ok())
+    } else {
+        None
+    };
+    let response = match send(url, cached.as_ref()).await {
+        Ok(response) => response,
+        Err(e) if cached.is_some() => {
+            warn!(
+                "⚠️ Could not reach {}, using the copy downloaded earlier: {}",
+                display_url(url),
+                e
+            );
+            return Ok(path);
+        }
+        Err(e) => return Err(e),
+    };
+    if response.status() == StatusCode::NOT_MODIFIED && cached.is_some() {
+        info!("📦 Using the cached download of {}", display_url(url));
+        return Ok(path);
+    }
+    if !response.status().is_success() {
+        return Err(describe_failure(response).await);
+    }
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value: &HeaderValue| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let entry = CacheEntry {
+        etag: header(ETAG),
+        last_modified: header(LAST_MODIFIED),
+    };
+    download(response, &path, display_url(url), show_progress).await?;
+    // Without the entry the archive is simply downloaded again next time, so this can't fail the
+    // run.
+    let _ = std::fs::write(&entry_path, serde_json::to_vec(&entry).unwrap());
+    Ok(path)
+}
+
+/// Writes the body of the response to `path`, showing its progress. The body goes to a temporary
+/// file first, so an interrupted download never leaves a truncated archive in the cache.
+async fn download(
+    response: Response<Body>,
+    path: &Path,
+    name: &str,
+    show_progress: bool,
+) -> Result<(), String> {
+    let length = response
+        .headers()
+        .get(hyper::header::CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<u64>().ok());
+    let pb = match (show_progress, length) {
+        (false, _) => ProgressBar::hidden(),
+        (true, Some(length)) => {
+            let pb = ProgressBar::new(length);
+            pb.set_style(
+                ProgressStyle::default_bar().template(
+                    "{msg} {wide_bar} {bytes}/{total_bytes} ({bytes_per_sec}, ETA {eta})",
+                ),
+            );
+            pb
+        }
+        (true, None) => {
+            let pb = ProgressBar::new_spinner();
+            pb.set_style(
+                ProgressStyle::default_spinner().template("{spinner} {msg} {bytes} ({elapsed})"),
+            );
+            pb
+        }
+    };
+    pb.set_message(format!("Downloading {}", name));
+    let partial = path.with_extension("zip.part");
+    let mut file = tokio::fs::File::create(&partial)
+        .await
+        .map_err(|e| format!("{}: {}", partial.display(), e))?;
+    let mut body = response.into_body();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| format!("the download stopped: {}", e))?;
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("{}: {}", partial.display(), e))?;
+        pb.inc(chunk.len() as u64);
+    }
+    file.flush()
+        .await
+        .map_err(|e| format!("{}: {}", partial.display(), e))?;
+    drop(file);
+    pb.finish_and_clear();
+    std::fs::rename(&partial, path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    info!(
+        "📦 Downloaded {} ({})",
+        name,
+        indicatif::HumanBytes(pb.position())
+    );
+    Ok(())
+}
+
+/// Turns an unsuccessful response into an error message, including the reason S3 gives in its
+/// XML error body if there is one.
+async fn describe_failure(response: Response<Body>) -> String {
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body())
+        .await
+        .unwrap_or_default();
+    let body = String::from_utf8_lossy(&body);
+    let element = |name: &str| {
+        let start = body.find(&format!("<{}>", name))? + name.len() + 2;
+        let end = start + body[start..].find(&format!("</{}>", name))?;
+        Some(body[start..end].to_string())
+    };
+    match (element("Code"), element("Message")) {
+        (Some(code), Some(message)) => format!("{} ({}: {})", status, code, message),
+        _ if status == StatusCode::NOT_FOUND => format!("{}, there's no file at this URL", status),
+        _ => status.to_string(),
+    }
+}
+
+/// Requests the archive, asking the server to answer with 304 Not Modified if it hasn't changed
+/// since the cached download.
+async fn send(url: &str, cached: Option<&CacheEntry>) -> Result<Response<Body>, String> {
+    let conditional = |mut request: hyper::http::request::Builder| {
+        if let Some(cached) = cached {
+            if let Some(etag) = &cached.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        request
+    };
+    if let Some(location) = url.strip_prefix("s3://") {
+        let (bucket, key) = location
+            .split_once('/')
+            .filter(|(bucket, key)| !bucket.is_empty() && !key.is_empty())
+            .ok_or("S3 URLs look like s3://<bucket>/<key>")?;
+        let request = s3_request(bucket, key, conditional, Utc::now())?;
+        return crate::net::client()
+            .request(request)
+            .await
+            .map_err(|e| format!("could not connect: {}", e));
+    }
+    let mut url = url::Url::parse(url).map_err(|e| e.to_string())?;
+    for _ in 0..=MAX_REDIRECTS {
+        let request = conditional(Request::get(url.as_str()))
+            .body(Body::empty())
+            .map_err(|e| e.to_string())?;
+        let response = crate::net::client()
+            .request(request)
+            .await
+            .map_err(|e| format!("could not connect: {}", e))?;
+        if !response.status().is_redirection() || response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(response);
+        }
+        let location = response
+            .headers()
+            .get(hyper::header::LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .ok_or_else(|| format!("{} without a Location to redirect to", response.status()))?;
+        url = url.join(location).map_err(|e| e.to_string())?;
+    }
+    Err(format!("more than {} redirects", MAX_REDIRECTS))
+}
+
+/// AWS credentials, found the same way the AWS command line tools do: from the environment, or
+/// from the shared credentials file for the selected profile.
+struct Credentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+/// Returns the AWS profile the credentials and region are read from.
+fn profile() -> String {
+    std::env::var("AWS_PROFILE").unwrap_or_else(|_| "default".to_string())
+}
+
+/// Returns the path to a file in `~/.aws`, unless the environment variable overrides it.
+fn aws_file(variable: &str, name: &str) -> Option<PathBuf> {
+    std::env::var_os(variable)
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".aws").join(name)))
+}
+
+/// Reads the keys in a section of an INI file like `~/.aws/credentials`.
+fn read_ini_section(path: &Path, section: &str) -> HashMap<String, String> {
+    let contents = std::fs::read_to_string(path).unwrap_or_default();
+    let mut current = None;
+    let mut values = HashMap::new();
+    for line in contents.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = Some(name.trim().to_string());
+        } else if current.as_deref() == Some(section) {
+            if let Some((key, value)) = line.split_once('=') {
+                values.insert(key.trim().to_string(), value.trim().to_string());
+            }
+        }
+    }
+    values
+}
+
+fn credentials() -> Option<Credentials> {
+    let env = |name| {
+        std::env::var(name)
+            .ok()
+            .filter(|value: &String| !value.is_empty())
+    };
+    if let (Some(access_key_id), Some(secret_access_key)) =
+        (env("AWS_ACCESS_KEY_ID"), env("AWS_SECRET_ACCESS_KEY"))
+    {
+        return Some(Credentials {
+            access_key_id,
+            secret_access_key,
+            session_token: env("AWS_SESSION_TOKEN"),
+        });
+    }
+    let path = aws_file("AWS_SHARED_CREDENTIALS_FILE", "credentials")?;
+    let mut section = read_ini_section(&path, &profile());
+    Some(Credentials {
+        access_key_id: section.remove("aws_access_key_id")?,
+        secret_access_key: section.remove("aws_secret_access_key")?,
+        session_token: section.remove("aws_session_token"),
+    })
+}
+
+/// Returns the region of the bucket, from the environment or the config file for the selected
+/// profile.
+fn region() -> String {
+    std::env::var("AWS_REGION")
+        .or_else(|_| std::env::var("AWS_DEFAULT_REGION"))
+        .ok()
+        .or_else(|| {
+            let profile = profile();
+            let section = if profile == "default" {
+                profile
+            } else {
+                format!("profile {}", profile)
+            };
+            aws_file("AWS_CONFIG_FILE", "config")
+                .and_then(|path| read_ini_section(&path, &section).remove("region"))
+        })
+        .unwrap_or_else(|| "us-east-1".to_string())
+}
+
+/// The characters S3 expects to be percent-encoded in the path of a signed request.
+const S3_PATH: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~')
+    .remove(b'/');
+
+/// Builds a GET request for an object in S3, signed with Signature Version 4 if there are
+/// credentials and sent anonymously, for public buckets, if not. `AWS_ENDPOINT_URL` points it at
+/// an S3-compatible service instead.
+fn s3_request(
+    bucket: &str,
+    key: &str,
+    conditional: impl FnOnce(hyper::http::request::Builder) -> hyper::http::request::Builder,
+    now: DateTime<Utc>,
+) -> Result<Request<Body>, String> {
+    let region = region();
+    let key = utf8_percent_encode(key, S3_PATH);
+    let url =
+        match std::env::var("AWS_ENDPOINT_URL_S3").or_else(|_| std::env::var("AWS_ENDPOINT_URL")) {
+            Ok(endpoint) => format!("{}/{}/{}", endpoint.trim_end_matches('/'), bucket, key),
+            // Bucket names with dots don't match the certificate as a subdomain.
+            Err(_) if bucket.contains('.') => {
+                format!("https://s3.{}.amazonaws.com/{}/{}", region, bucket, key)
+            }
+            Err(_) => format!("https://{}.s3.{}.amazonaws.com/{}", bucket, region, key),
+        };
+    let uri = url.parse::<Uri>().map_err(|e| e.to_string())?;
+    let mut request = conditional(Request::get(&uri));
+    if let Some(credentials) = credentials() {
+        let host = uri.authority().map_or("", |authority| authority.as_str());
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let mut headers = vec![
+            ("host", host.to_string()),
+            ("x-amz-content-sha256", "UNSIGNED-PAYLOAD".to_string()),
+            ("x-amz-date", amz_date),
+        ];
+        if let Some(token) = &credentials.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        let authorization = sign(&credentials, &region, uri.path(), &headers, now);
+        for (name, value) in headers.into_iter().filter(|(name, _)| *name != "host") {
+            request = request.header(name, value);
+        }
+        request = request.header(hyper::header::AUTHORIZATION, authorization);
+    }
+    request.body(Body::empty()).map_err(|e| e.to_string())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Returns the Signature Version 4 `Authorization` header for a GET request to S3 with the given
+/// path and headers, which must be lowercase and sorted by name and include `x-amz-date` and
+/// `x-amz-content-sha256`.
+fn sign(
+    credentials: &Credentials,
+    region: &str,
+    path: &str,
+    headers: &[(&str, String)],
+    now: DateTime<Utc>,
+) -> String {
+    let date = now.format("%Y%m%d").to_string();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let canonical_headers = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect::<String>();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let payload_hash = headers
+        .iter()
+        .find(|(name, _)| *name == "x-amz-content-sha256")
+        .map_or("UNSIGNED-PAYLOAD", |(_, value)| value.as_str());
+    let canonical_request = format!(
+        "GET\n{}\n\n{}\n{}\n{}",
+        path, canonical_headers, signed_headers, payload_hash
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let key = [region, "s3", "aws4_request"].iter().fold(
+        hmac(
+            format!("AWS4{}", credentials.secret_access_key).as_bytes(),
+            &date,
+        ),
+        |key, part| hmac(&key, part),
+    );
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        credentials.access_key_id,
+        scope,
+        signed_headers,
+        hex(&hmac(&key, &string_to_sign))
+    )
+}
@@ -0,0 +1,223 @@
+//! The full-screen dashboard shown while deleting with --tui. It's drawn on the terminal's
+//! alternate screen from the counters the rest of the run updates, on a timer.
+
+use crossterm::style::Print;
+use crossterm::{cursor, execute, queue, terminal};
+use indicatif::{FormattedDuration, ProgressBar};
+use log::LevelFilter;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{oneshot, Mutex};
+use tokio::task::JoinHandle;
+use unicode_width::UnicodeWidthChar;
+
+/// The smallest terminal the dashboard is drawn in, in columns and rows.
+const MIN_SIZE: (u16, u16) = (60, 16);
+
+/// How often the dashboard is redrawn.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How many failures are kept for the list of recent failures.
+pub const RECENT_FAILURES: usize = 50;
+
+/// Whether the dashboard is on the screen, so [`restore`] knows whether there's anything to undo.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// The state of the run the dashboard shows, shared with the tasks that update it.
+pub struct Counters {
+    /// What the run does, like "Damae: delete tweets".
+    pub title: String,
+    /// Tracks how many items were processed, and how fast.
+    pub pb: ProgressBar,
+    pub deleted: Arc<AtomicUsize>,
+    pub already_deleted: Arc<AtomicUsize>,
+    pub failed: Arc<AtomicUsize>,
+    pub error_codes: Arc<Mutex<BTreeMap<i32, usize>>>,
+    /// The latest failures, newest last, as `<id>: <error>`.
+    pub recent_failures: Arc<Mutex<VecDeque<String>>>,
+    pub rate_limit_reset: Arc<Mutex<Option<i32>>>,
+    pub stop: Arc<AtomicBool>,
+}
+
+/// Returns why the dashboard can't be shown, if it can't.
+pub fn unavailable() -> Option<&'static str> {
+    if !std::io::stdout().is_terminal() {
+        return Some("The output isn't a terminal");
+    }
+    match terminal::size() {
+        Ok((columns, rows)) if columns >= MIN_SIZE.0 && rows >= MIN_SIZE.1 => None,
+        Ok(_) => Some("The terminal is too small"),
+        Err(_) => Some("The size of the terminal is unknown"),
+    }
+}
+
+/// The dashboard on the screen, which stays until [`Dashboard::finish`] is called.
+pub struct Dashboard {
+    done: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+    max_level: LevelFilter,
+}
+
+impl Dashboard {
+    /// Stops redrawing, and switches back to the normal screen.
+    pub async fn finish(self) {
+        let _ = self.done.send(());
+        let _ = self.task.await;
+        restore();
+        log::set_max_level(self.max_level);
+    }
+}
+
+/// Switches to the alternate screen and starts drawing the dashboard.
+pub fn start(counters: Counters) -> crossterm::Result<Dashboard> {
+    execute!(
+        std::io::stdout(),
+        terminal::EnterAlternateScreen,
+        cursor::Hide
+    )?;
+    ACTIVE.store(true, Ordering::SeqCst);
+    // Log lines would be drawn over the dashboard, which shows the failures itself.
+    let max_level = log::max_level();
+    log::set_max_level(LevelFilter::Off);
+    let (done, mut done_signal) = oneshot::channel::<()>();
+    let task = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = &mut done_signal => break,
+            }
+            let lines = render(&counters).await;
+            let _ = draw(&lines);
+        }
+    });
+    Ok(Dashboard {
+        done,
+        task,
+        max_level,
+    })
+}
+
+/// Switches back to the normal screen if the dashboard is shown, so the terminal is usable again
+/// when the process exits in the middle of a run.
+pub fn restore() {
+    if ACTIVE.swap(false, Ordering::SeqCst) {
+        let _ = execute!(
+            std::io::stdout(),
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+    }
+}
+
+/// Returns the lines of the dashboard, to fit the terminal's current size.
+async fn render(counters: &Counters) -> Vec<String> {
+    let (columns, rows) = terminal::size().unwrap_or(MIN_SIZE);
+    if columns < MIN_SIZE.0 || rows < MIN_SIZE.1 {
+        return vec!["The terminal is too small for the dashboard, make it bigger".to_string()];
+    }
+    let (columns, rows) = (usize::from(columns), usize::from(rows));
+    let pb = &counters.pb;
+    let (position, total) = (pb.position(), pb.length().max(1));
+    let elapsed = pb.elapsed();
+    let mut lines = vec![
+        format!("{}, {} elapsed", counters.title, FormattedDuration(elapsed)),
+        String::new(),
+    ];
+
+    let counts = format!(" {}/{} ({}%)", position, total, position * 100 / total);
+    let bar_width = columns.saturating_sub(counts.len() + 2);
+    let filled = (bar_width as u64 * position / total) as usize;
+    lines.push(format!(
+        "[{}{}]{}",
+        "█".repeat(filled),
+        "░".repeat(bar_width - filled),
+        counts
+    ));
+    lines.push(format!(
+        "Deleted {}, already gone {}, failed {}",
+        counters.deleted.load(Ordering::SeqCst),
+        counters.already_deleted.load(Ordering::SeqCst),
+        counters.failed.load(Ordering::SeqCst)
+    ));
+    let per_sec = position as f64 / elapsed.as_secs_f64().max(1.0);
+    let mut rate = format!("{:.1}/s, ETA {}", per_sec, FormattedDuration(pb.eta()));
+    if let Some(reset) = *counters.rate_limit_reset.lock().await {
+        let wait = i64::from(reset) - chrono::Utc::now().timestamp();
+        if wait > 0 {
+            rate.push_str(&format!(
+                ", rate limited for {}",
+                FormattedDuration(Duration::from_secs(wait as u64))
+            ));
+        }
+    }
+    lines.push(rate);
+    if counters.stop.load(Ordering::SeqCst) {
+        lines.push(
+            "🛑 Stopping after the deletions in progress finish, press Ctrl-C again to quit now"
+                .to_string(),
+        );
+    }
+
+    lines.push(String::new());
+    lines.push("Error codes:".to_string());
+    let error_codes = counters.error_codes.lock().await;
+    if error_codes.is_empty() {
+        lines.push("  none".to_string());
+    }
+    let mut error_codes = error_codes.iter().collect::<Vec<_>>();
+    error_codes.sort_by(|(_, a), (_, b)| b.cmp(a));
+    for (code, count) in error_codes.into_iter().take(5) {
+        lines.push(format!("  {:>5}  {}", code, count));
+    }
+
+    // The failures fill the rest of the screen, newest first.
+    lines.push(String::new());
+    lines.push("Recent failures:".to_string());
+    let recent_failures = counters.recent_failures.lock().await;
+    if recent_failures.is_empty() {
+        lines.push("  none".to_string());
+    }
+    let room = rows.saturating_sub(lines.len());
+    lines.extend(
+        recent_failures
+            .iter()
+            .rev()
+            .take(room)
+            .map(|failure| format!("  {}", failure)),
+    );
+    lines
+        .iter()
+        .take(rows)
+        .map(|line| cut(line, columns))
+        .collect()
+}
+
+/// Cuts a line off at `width` columns, so it doesn't wrap onto the next one.
+fn cut(line: &str, width: usize) -> String {
+    let mut used = 0;
+    line.chars()
+        .take_while(|c| {
+            used += c.width().unwrap_or(0);
+            used <= width
+        })
+        .collect()
+}
+
+/// Replaces what's on the screen with the lines.
+fn draw(lines: &[String]) -> crossterm::Result<()> {
+    let mut stdout = std::io::stdout();
+    queue!(
+        stdout,
+        cursor::MoveTo(0, 0),
+        terminal::Clear(terminal::ClearType::All)
+    )?;
+    for (row, line) in lines.iter().enumerate() {
+        queue!(stdout, cursor::MoveTo(0, row as u16), Print(line))?;
+    }
+    stdout.flush()?;
+    Ok(())
+}